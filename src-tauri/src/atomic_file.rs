@@ -0,0 +1,31 @@
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+// 先写同目录下的临时文件并刷到磁盘，再重命名覆盖目标文件
+// 进程中途退出或断电时目标文件要么是旧内容，要么是新内容，不会只写了一半
+pub fn write(path: &Path, data: &[u8]) -> Result<(), String> {
+    let tmp_path = tmp_path(path);
+    let result = std::fs::File::create(&tmp_path)
+        .and_then(|mut file| {
+            file.write_all(data)?;
+            file.sync_all()
+        })
+        .and_then(|_| std::fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = std::fs::remove_file(&tmp_path);
+    }
+    result.map_err(|e| e.to_string())
+}
+
+pub async fn write_async(path: &Path, data: Vec<u8>) -> Result<(), String> {
+    let path = path.to_path_buf();
+    tokio::task::spawn_blocking(move || write(&path, &data))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+fn tmp_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".tmp");
+    PathBuf::from(name)
+}
@@ -0,0 +1,165 @@
+use crate::typ::Checksum;
+use aws_sdk_s3::types::ChecksumAlgorithm;
+use base64::{engine::general_purpose, Engine};
+use md5::Md5;
+use sha2::{Digest, Sha256};
+use tokio::io::AsyncReadExt;
+
+// 增量计算校验和，得到的是原始摘要字节，S3 请求头使用其 base64 形式
+pub enum Hasher {
+    Sha256(Sha256),
+    Crc32c(u32),
+}
+
+impl Hasher {
+    pub fn new(checksum: Checksum) -> Option<Self> {
+        match checksum {
+            Checksum::None => None,
+            Checksum::Sha256 => Some(Self::Sha256(Sha256::new())),
+            Checksum::Crc32c => Some(Self::Crc32c(0)),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Self::Sha256(hasher) => hasher.update(data),
+            Self::Crc32c(crc) => *crc = crc32c::crc32c_append(*crc, data),
+        }
+    }
+
+    pub fn finalize(self) -> Vec<u8> {
+        match self {
+            Self::Sha256(hasher) => hasher.finalize().to_vec(),
+            Self::Crc32c(crc) => crc.to_be_bytes().to_vec(),
+        }
+    }
+}
+
+pub fn algorithm(checksum: Checksum) -> Option<ChecksumAlgorithm> {
+    match checksum {
+        Checksum::None => None,
+        Checksum::Sha256 => Some(ChecksumAlgorithm::Sha256),
+        Checksum::Crc32c => Some(ChecksumAlgorithm::Crc32C),
+    }
+}
+
+// 写入对象元数据时使用的键，即 x-amz-meta-sha256 / x-amz-meta-crc32c
+pub fn metadata_key(checksum: Checksum) -> &'static str {
+    match checksum {
+        Checksum::None => "",
+        Checksum::Sha256 => "sha256",
+        Checksum::Crc32c => "crc32c",
+    }
+}
+
+// 单个分段或小文件的校验和（base64）
+pub fn digest(checksum: Checksum, data: &[u8]) -> Option<String> {
+    let mut hasher = Hasher::new(checksum)?;
+    hasher.update(data);
+    Some(general_purpose::STANDARD.encode(hasher.finalize()))
+}
+
+// 整个文件的校验和（base64），分段上传前顺序读一遍文件得到，写入对象元数据
+pub async fn file_digest(checksum: Checksum, path: &str) -> Result<Option<String>, String> {
+    let Some(mut hasher) = Hasher::new(checksum) else {
+        return Ok(None);
+    };
+    read_chunks(path, |chunk| hasher.update(chunk)).await?;
+    Ok(Some(general_purpose::STANDARD.encode(hasher.finalize())))
+}
+
+// 十六进制的 MD5，用于和单次 PUT 上传的对象的 ETag 比较
+pub fn md5(data: &[u8]) -> String {
+    hex(&Md5::digest(data))
+}
+
+pub async fn file_md5(path: &str) -> Result<String, String> {
+    let mut hasher = Md5::new();
+    read_chunks(path, |chunk| hasher.update(chunk)).await?;
+    Ok(hex(&hasher.finalize()))
+}
+
+pub fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+async fn read_chunks(path: &str, mut f: impl FnMut(&[u8])) -> Result<(), String> {
+    let mut file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| e.to_string())?;
+    let mut buffer = vec![0; 1024 * 1024];
+    loop {
+        let n = file.read(&mut buffer).await.map_err(|e| e.to_string())?;
+        if n == 0 {
+            return Ok(());
+        }
+        f(&buffer[..n]);
+    }
+}
+
+// 分段上传完成后对象的组合校验和：对各分段摘要拼接后再做一次摘要，格式为 base64-分段数
+pub fn composite(checksum: Checksum, part_checksums: &[&str]) -> Result<Option<String>, String> {
+    let Some(mut hasher) = Hasher::new(checksum) else {
+        return Ok(None);
+    };
+    for part_checksum in part_checksums {
+        let raw = general_purpose::STANDARD
+            .decode(part_checksum)
+            .map_err(|e| e.to_string())?;
+        hasher.update(&raw);
+    }
+
+    Ok(Some(format!(
+        "{}-{}",
+        general_purpose::STANDARD.encode(hasher.finalize()),
+        part_checksums.len()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn digest_matches_known_vectors() {
+        assert_eq!(
+            digest(Checksum::Sha256, b"abc").as_deref(),
+            Some("ungWv48Bz+pBQUDeXa4iI7ADYaOWF3qctBD/YfIAFa0=")
+        );
+        assert_eq!(
+            digest(Checksum::Crc32c, b"123456789").as_deref(),
+            Some("4waSgw==")
+        );
+        assert_eq!(digest(Checksum::None, b"abc"), None);
+    }
+
+    #[test]
+    fn composite_hashes_concatenated_part_digests() {
+        let parts = [
+            digest(Checksum::Sha256, b"part one").unwrap(),
+            digest(Checksum::Sha256, b"part two").unwrap(),
+        ];
+        let parts: Vec<&str> = parts.iter().map(String::as_str).collect();
+        assert_eq!(
+            composite(Checksum::Sha256, &parts).unwrap().as_deref(),
+            Some("ZcfzJvHWAX9vO7fPKA9XUU5dtZ+mmrAFxgKDQljlC2k=-2")
+        );
+
+        let parts = [
+            digest(Checksum::Crc32c, b"part one").unwrap(),
+            digest(Checksum::Crc32c, b"part two").unwrap(),
+        ];
+        let parts: Vec<&str> = parts.iter().map(String::as_str).collect();
+        assert_eq!(parts, ["foplgw==", "BsZkyQ=="]);
+        assert_eq!(
+            composite(Checksum::Crc32c, &parts).unwrap().as_deref(),
+            Some("WX4d1g==-2")
+        );
+    }
+
+    #[test]
+    fn composite_handles_disabled_and_invalid_input() {
+        assert_eq!(composite(Checksum::None, &["foplgw=="]), Ok(None));
+        assert!(composite(Checksum::Sha256, &["not base64!"]).is_err());
+    }
+}
@@ -0,0 +1,126 @@
+use crate::typ::UploadSource;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+
+// 解码后的内存内容，content_type 来自 data URL 的头部
+pub struct Content {
+    pub bytes: Vec<u8>,
+    pub content_type: Option<String>,
+}
+
+// 把内存中的上传来源解码成字节，本地文件和远程 URL 返回 None
+// FileContent 以 data: 开头时按 data URL 解码，否则按 UTF-8 文本上传
+pub fn decode(source: &UploadSource) -> Result<Option<Content>, String> {
+    match source {
+        UploadSource::FilePath(_) | UploadSource::Url { .. } => Ok(None),
+        UploadSource::FileContent(content) => match content.strip_prefix("data:") {
+            Some(data_url) => decode_data_url(data_url).map(Some),
+            None => Ok(Some(Content {
+                bytes: content.as_bytes().to_vec(),
+                content_type: None,
+            })),
+        },
+        UploadSource::Base64(data) => Ok(Some(Content {
+            bytes: decode_base64(data)?,
+            content_type: None,
+        })),
+    }
+}
+
+// data:[<mediatype>][;base64],<data>
+fn decode_data_url(data_url: &str) -> Result<Content, String> {
+    let (header, data) = data_url
+        .split_once(',')
+        .ok_or_else(|| "Invalid data URL: missing ','".to_string())?;
+
+    let (media_type, is_base64) = match header.strip_suffix(";base64") {
+        Some(media_type) => (media_type, true),
+        None => (header, false),
+    };
+    let bytes = if is_base64 {
+        decode_base64(data)?
+    } else {
+        percent_decode(data)?
+    };
+    let content_type = Some(media_type.trim())
+        .filter(|m| !m.is_empty())
+        .map(str::to_string);
+
+    Ok(Content {
+        bytes,
+        content_type,
+    })
+}
+
+// 允许内容中夹带换行等空白
+fn decode_base64(data: &str) -> Result<Vec<u8>, String> {
+    let data: String = data.chars().filter(|c| !c.is_ascii_whitespace()).collect();
+    STANDARD
+        .decode(data)
+        .map_err(|e| format!("Invalid base64 content: {}", e))
+}
+
+pub fn percent_decode(data: &str) -> Result<Vec<u8>, String> {
+    let bytes = data.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = data
+                .get(i + 1..i + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| format!("Invalid percent-encoding at position {}", i))?;
+            decoded.push(hex);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_data_url_reads_base64_and_media_type() {
+        let content = decode_data_url("image/png;base64,aGVs\r\nbG8=").unwrap();
+        assert_eq!(content.bytes, b"hello");
+        assert_eq!(content.content_type.as_deref(), Some("image/png"));
+    }
+
+    #[test]
+    fn decode_data_url_reads_percent_encoded_text() {
+        let content = decode_data_url("text/plain;charset=utf-8,%E4%BD%A0 %2525").unwrap();
+        assert_eq!(content.bytes, "你 %25".as_bytes());
+        assert_eq!(
+            content.content_type.as_deref(),
+            Some("text/plain;charset=utf-8")
+        );
+
+        let content = decode_data_url(",hello").unwrap();
+        assert_eq!(content.bytes, b"hello");
+        assert_eq!(content.content_type, None);
+    }
+
+    #[test]
+    fn decode_data_url_rejects_malformed_input() {
+        assert!(decode_data_url("text/plain;base64").is_err());
+        assert!(decode_data_url(";base64,not base64!").is_err());
+        assert!(decode_data_url(",%zz").is_err());
+        assert!(decode_data_url(",%4").is_err());
+    }
+
+    #[test]
+    fn decode_treats_plain_file_content_as_text() {
+        let source = UploadSource::FileContent("hello".to_string());
+        let content = decode(&source).unwrap().unwrap();
+        assert_eq!(content.bytes, b"hello");
+        assert_eq!(content.content_type, None);
+
+        let source = UploadSource::FilePath("/tmp/a.txt".to_string());
+        assert!(decode(&source).unwrap().is_none());
+    }
+}
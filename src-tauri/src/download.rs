@@ -0,0 +1,181 @@
+use crate::atomic_file;
+use crate::typ::DownloadState;
+use std::path::{Component, Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// 上传工具常用的修改时间元数据（x-amz-meta-mtime），值为 Unix 秒，可带小数
+pub const MTIME_METADATA_KEY: &str = "mtime";
+
+// 远端 key 映射到本地路径，拒绝 .. 和绝对路径，避免写到目标目录之外
+pub fn local_path(target_dir: &str, relative_key: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(relative_key.trim_start_matches('/'));
+    if relative.as_os_str().is_empty()
+        || relative
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+    {
+        return Err(format!("Invalid object key for download: {}", relative_key));
+    }
+    Ok(Path::new(target_dir).join(relative))
+}
+
+pub fn part_path(path: &Path) -> PathBuf {
+    append_extension(path, "part")
+}
+
+fn state_path(path: &Path) -> PathBuf {
+    append_extension(path, "part.json")
+}
+
+fn append_extension(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".");
+    name.push(extension);
+    PathBuf::from(name)
+}
+
+pub fn chunk_count(size: u64, chunk_size: u64) -> u64 {
+    size.div_ceil(chunk_size)
+}
+
+// 第 index 段的起始偏移和长度，最后一段可能不足 chunk_size
+pub fn chunk_range(size: u64, chunk_size: u64, index: u64) -> (u64, u64) {
+    let start = index * chunk_size;
+    (start, chunk_size.min(size - start))
+}
+
+// .part 文件和进度文件都在时才能续传，缺一个就从头下载
+pub async fn load_state(path: &Path) -> Option<DownloadState> {
+    if tokio::fs::metadata(part_path(path)).await.is_err() {
+        return None;
+    }
+    let data = tokio::fs::read(state_path(path)).await.ok()?;
+    serde_json::from_slice(&data).ok()
+}
+
+pub async fn save_state(path: &Path, state: &DownloadState) -> Result<(), String> {
+    let data = serde_json::to_vec(state).map_err(|e| e.to_string())?;
+    atomic_file::write_async(&state_path(path), data).await
+}
+
+pub async fn remove_state(path: &Path) -> Result<(), String> {
+    match tokio::fs::remove_file(state_path(path)).await {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+pub fn parse_mtime(value: &str) -> Option<SystemTime> {
+    let secs: f64 = value.trim().parse().ok()?;
+    if !secs.is_finite() || secs < 0.0 {
+        return None;
+    }
+    Some(UNIX_EPOCH + Duration::from_secs_f64(secs))
+}
+
+pub async fn set_mtime(path: &Path, mtime: SystemTime) -> Result<(), String> {
+    let file = tokio::fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .await
+        .map_err(|e| e.to_string())?
+        .into_std()
+        .await;
+    tokio::task::spawn_blocking(move || file.set_modified(mtime))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 每个测试用独立的临时目录，避免并行运行时互相干扰
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir =
+            std::env::temp_dir().join(format!("crate-download-{}-{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn chunks_cover_the_object_and_last_one_is_short() {
+        assert_eq!(chunk_count(0, 8), 0);
+        assert_eq!(chunk_count(16, 8), 2);
+        assert_eq!(chunk_count(17, 8), 3);
+
+        let ranges: Vec<_> = (0..chunk_count(17, 8))
+            .map(|index| chunk_range(17, 8, index))
+            .collect();
+        assert_eq!(ranges, vec![(0, 8), (8, 8), (16, 1)]);
+        assert_eq!(ranges.iter().map(|(_, len)| len).sum::<u64>(), 17);
+    }
+
+    #[test]
+    fn local_path_stays_inside_target_dir() {
+        assert_eq!(
+            local_path("/data", "/photos/a.jpg").unwrap(),
+            Path::new("/data/photos/a.jpg")
+        );
+        assert!(local_path("/data", "").is_err());
+        assert!(local_path("/data", "/").is_err());
+        assert!(local_path("/data", "photos/../../etc/passwd").is_err());
+        assert!(local_path("/data", "./a.jpg").is_err());
+    }
+
+    #[test]
+    fn part_and_state_paths_append_extensions() {
+        let path = Path::new("/data/archive.tar.gz");
+        assert_eq!(part_path(path), Path::new("/data/archive.tar.gz.part"));
+        assert_eq!(
+            state_path(path),
+            Path::new("/data/archive.tar.gz.part.json")
+        );
+        assert_eq!(part_path(Path::new("README")), Path::new("README.part"));
+    }
+
+    #[test]
+    fn parse_mtime_accepts_unix_seconds() {
+        assert_eq!(
+            parse_mtime("1700000000"),
+            Some(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+        );
+        assert_eq!(
+            parse_mtime(" 1.5 "),
+            Some(UNIX_EPOCH + Duration::from_millis(1500))
+        );
+        assert_eq!(parse_mtime("-1"), None);
+        assert_eq!(parse_mtime("NaN"), None);
+        assert_eq!(parse_mtime("inf"), None);
+        assert_eq!(parse_mtime("yesterday"), None);
+    }
+
+    #[tokio::test]
+    async fn state_is_resumable_only_with_part_file() {
+        let dir = temp_dir("state");
+        let path = dir.join("video.mp4");
+        let state = DownloadState {
+            e_tag: "\"etag\"".to_string(),
+            size: 17,
+            chunk_size: 8,
+            completed_chunks: vec![0, 2],
+        };
+
+        save_state(&path, &state).await.unwrap();
+        assert!(load_state(&path).await.is_none());
+
+        std::fs::write(part_path(&path), b"").unwrap();
+        let loaded = load_state(&path).await.unwrap();
+        assert_eq!(loaded.e_tag, state.e_tag);
+        assert_eq!(loaded.completed_chunks, state.completed_chunks);
+
+        remove_state(&path).await.unwrap();
+        assert!(load_state(&path).await.is_none());
+        remove_state(&path).await.unwrap();
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,190 @@
+use crate::connector;
+use hyper::body::HttpBody;
+use hyper::header::{
+    HeaderName, AUTHORIZATION, CONTENT_TYPE, COOKIE, LOCATION, PROXY_AUTHORIZATION,
+};
+use hyper::{Body, Request, Uri};
+use std::collections::HashMap;
+use std::time::Duration;
+
+const MAX_REDIRECTS: usize = 10;
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30); // 建立连接并收到响应头的超时
+const READ_TIMEOUT: Duration = Duration::from_secs(60); // 响应体超过这么久没有新数据时视为连接已断开
+
+// 远程资源的响应流，按分段大小顺序读取，不落地临时文件
+pub struct Download {
+    body: Body,
+    // 上一次读取多出来的数据，或被退回的数据
+    pending: Vec<u8>,
+    content_length: Option<u64>,
+    content_type: Option<String>,
+    max_bytes: Option<u64>,
+    received: u64,
+}
+
+// 发起 GET 请求，经过代理设置并跟随重定向；Content-Length 已知且超过上限时直接失败
+pub async fn open(
+    url: &str,
+    headers: &HashMap<String, String>,
+    max_bytes: Option<u64>,
+) -> Result<Download, String> {
+    let client = connector::fetch_client(&connector::proxy())?;
+    let mut uri: Uri = url
+        .parse()
+        .map_err(|e| format!("Invalid URL {}: {}", url, e))?;
+    let mut headers = headers.clone();
+
+    let mut redirects = 0;
+    let response = loop {
+        let mut request = Request::get(uri.clone());
+        for (key, value) in &headers {
+            request = request.header(key.as_str(), value.as_str());
+        }
+        let request = request.body(Body::empty()).map_err(|e| e.to_string())?;
+        let response = tokio::time::timeout(CONNECT_TIMEOUT, client.request(request))
+            .await
+            .map_err(|_| format!("Timed out waiting for a response from {}", uri))?
+            .map_err(|e| e.to_string())?;
+
+        let location = response
+            .headers()
+            .get(LOCATION)
+            .and_then(|v| v.to_str().ok());
+        let Some(location) = location.filter(|_| response.status().is_redirection()) else {
+            break response;
+        };
+        if redirects == MAX_REDIRECTS {
+            return Err(format!("Too many redirects for {}", url));
+        }
+        redirects += 1;
+
+        let target = redirect_target(&uri, location)?;
+        // 跳转到其他主机时不再发送凭据
+        if target.authority() != uri.authority() {
+            headers.retain(|key, _| !is_credential(key));
+        }
+        uri = target;
+    };
+
+    if !response.status().is_success() {
+        return Err(format!(
+            "Request to {} failed with status {}",
+            uri,
+            response.status()
+        ));
+    }
+
+    let content_length = response.body().size_hint().exact();
+    if let (Some(length), Some(max)) = (content_length, max_bytes) {
+        if length > max {
+            return Err(format!(
+                "Remote file is too large: {} bytes (limit {} bytes)",
+                length, max
+            ));
+        }
+    }
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|v| v.to_str().ok())
+        .map(str::to_string);
+
+    Ok(Download {
+        body: response.into_body(),
+        pending: Vec::new(),
+        content_length,
+        content_type,
+        max_bytes,
+        received: 0,
+    })
+}
+
+// 解析 Location：绝对地址、协议相对地址、绝对路径、查询串或相对路径
+fn redirect_target(base: &Uri, location: &str) -> Result<Uri, String> {
+    let invalid =
+        |e: hyper::http::uri::InvalidUri| format!("Invalid redirect location {}: {}", location, e);
+    let parsed: Uri = location.parse().map_err(invalid)?;
+    if parsed.scheme().is_some() {
+        return Ok(parsed);
+    }
+
+    let scheme = base.scheme_str().unwrap_or("http");
+    let authority = base.authority().map_or("", |authority| authority.as_str());
+    let path = base.path();
+    let target = if let Some(rest) = location.strip_prefix("//") {
+        format!("{}://{}", scheme, rest)
+    } else if location.starts_with('/') {
+        format!("{}://{}{}", scheme, authority, location)
+    } else if location.starts_with('?') {
+        format!("{}://{}{}{}", scheme, authority, path, location)
+    } else {
+        let dir = &path[..path.rfind('/').map_or(0, |i| i + 1)];
+        format!("{}://{}{}{}", scheme, authority, dir, location)
+    };
+    target.parse().map_err(invalid)
+}
+
+fn is_credential(key: &str) -> bool {
+    HeaderName::from_bytes(key.as_bytes())
+        .is_ok_and(|name| name == AUTHORIZATION || name == COOKIE || name == PROXY_AUTHORIZATION)
+}
+
+impl Download {
+    pub fn content_length(&self) -> Option<u64> {
+        self.content_length
+    }
+
+    pub fn content_type(&self) -> Option<&str> {
+        self.content_type.as_deref()
+    }
+
+    // 读取最多 len 字节，只有流结束时才会少于 len，返回空表示已读完
+    pub async fn read(&mut self, len: usize) -> Result<Vec<u8>, String> {
+        while self.pending.len() < len {
+            let chunk = tokio::time::timeout(READ_TIMEOUT, self.body.data())
+                .await
+                .map_err(|_| {
+                    format!(
+                        "No data received from the remote server for {} seconds",
+                        READ_TIMEOUT.as_secs()
+                    )
+                })?;
+            match chunk {
+                Some(chunk) => {
+                    let chunk = chunk.map_err(|e| e.to_string())?;
+                    self.received += chunk.len() as u64;
+                    if let Some(max) = self.max_bytes {
+                        if self.received > max {
+                            return Err(format!(
+                                "Remote file exceeds the size limit of {} bytes",
+                                max
+                            ));
+                        }
+                    }
+                    self.pending.extend_from_slice(&chunk);
+                }
+                None => {
+                    if let Some(length) = self.content_length {
+                        if self.received < length {
+                            return Err(format!(
+                                "Connection closed after {} of {} bytes",
+                                self.received, length
+                            ));
+                        }
+                    }
+                    break;
+                }
+            }
+        }
+
+        let len = len.min(self.pending.len());
+        let rest = self.pending.split_off(len);
+        Ok(std::mem::replace(&mut self.pending, rest))
+    }
+
+    // 退回读多的数据，下次 read 时先返回
+    pub fn unread(&mut self, mut data: Vec<u8>) {
+        data.append(&mut self.pending);
+        self.pending = data;
+    }
+}
@@ -0,0 +1,310 @@
+use crate::typ::{HeaderRule, ObjectHeaders};
+use aws_sdk_s3::primitives::{DateTime, DateTimeFormat};
+use glob::{MatchOptions, Pattern};
+use infer::MatcherType;
+use mime_guess::from_path;
+
+// 用于识别类型的内容开头长度
+pub const SNIFF_LEN: usize = 8192;
+
+// 依次应用所有匹配的存储桶规则（靠后的规则优先），再用本次上传指定的请求头覆盖
+pub fn resolve(
+    rules: &[HeaderRule],
+    remote_filename: &str,
+    headers: &ObjectHeaders,
+) -> ObjectHeaders {
+    rules
+        .iter()
+        .filter(|rule| rule.matches(remote_filename))
+        .fold(ObjectHeaders::default(), |acc, rule| {
+            acc.merge(Some(&rule.headers))
+        })
+        .merge(Some(headers))
+}
+
+impl HeaderRule {
+    fn matches(&self, remote_filename: &str) -> bool {
+        let pattern = match Pattern::new(self.pattern.trim()) {
+            Ok(pattern) => pattern,
+            Err(e) => {
+                println!("忽略无效的请求头规则 {}：{}", self.pattern, e);
+                return false;
+            }
+        };
+
+        let remote_filename = remote_filename.trim_start_matches('/');
+        let options = MatchOptions {
+            case_sensitive: true,
+            require_literal_separator: true,
+            require_literal_leading_dot: false,
+        };
+        if self.pattern.contains('/') {
+            pattern.matches_with(remote_filename, options)
+        } else {
+            let name = remote_filename
+                .rsplit('/')
+                .next()
+                .unwrap_or(remote_filename);
+            pattern.matches_with(name, options)
+        }
+    }
+}
+
+impl ObjectHeaders {
+    // overrides 中非空的字段覆盖当前设置，元数据按键合并
+    pub fn merge(&self, overrides: Option<&ObjectHeaders>) -> ObjectHeaders {
+        let Some(overrides) = overrides else {
+            return self.clone();
+        };
+
+        let mut metadata = self.metadata.clone();
+        metadata.extend(overrides.metadata.clone());
+        ObjectHeaders {
+            content_type: pick(&overrides.content_type, &self.content_type),
+            cache_control: pick(&overrides.cache_control, &self.cache_control),
+            content_disposition: pick(&overrides.content_disposition, &self.content_disposition),
+            content_encoding: pick(&overrides.content_encoding, &self.content_encoding),
+            content_language: pick(&overrides.content_language, &self.content_language),
+            expires: pick(&overrides.expires, &self.expires),
+            metadata,
+        }
+    }
+
+    // 上传和规则都未指定时，根据远端文件名和内容开头的魔数推断
+    pub fn content_type(&self, remote_filename: &str, head: &[u8]) -> String {
+        non_empty(&self.content_type).unwrap_or_else(|| sniff(remote_filename, head))
+    }
+
+    pub fn expires(&self) -> Result<Option<DateTime>, String> {
+        self.expires
+            .as_deref()
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+            .map(|e| {
+                DateTime::from_str(e, DateTimeFormat::HttpDate)
+                    .map_err(|err| format!("Invalid Expires header {}: {}", e, err))
+            })
+            .transpose()
+    }
+
+    // SDK 会自动加上 x-amz-meta- 前缀，这里去掉用户可能带上的前缀并统一为小写
+    pub fn metadata(&self) -> impl Iterator<Item = (String, &String)> {
+        self.metadata.iter().map(|(key, value)| {
+            let key = key.trim().to_lowercase();
+            let key = key
+                .strip_prefix("x-amz-meta-")
+                .map(str::to_string)
+                .unwrap_or(key);
+            (key, value)
+        })
+    }
+}
+
+// 扩展名缺失时以魔数为准；扩展名和魔数都是图片/音视频但类型不同时（如改了后缀的截图）也以魔数为准
+fn sniff(remote_filename: &str, head: &[u8]) -> String {
+    let guessed = from_path(remote_filename).first();
+    let sniffed = infer::get(head);
+
+    let mime = match (guessed, sniffed) {
+        (None, Some(sniffed)) => sniffed.mime_type().to_string(),
+        (Some(guessed), Some(sniffed))
+            if guessed.essence_str() != sniffed.mime_type()
+                && matches!(
+                    sniffed.matcher_type(),
+                    MatcherType::Image | MatcherType::Audio | MatcherType::Video
+                ) =>
+        {
+            sniffed.mime_type().to_string()
+        }
+        (Some(guessed), _) => guessed.to_string(),
+        (None, None) if is_text(head) => "text/plain".to_string(),
+        (None, None) => "application/octet-stream".to_string(),
+    };
+
+    with_charset(mime)
+}
+
+// 没有 NUL 且是合法 UTF-8 的内容视为文本，末尾被截断的多字节字符不算错误
+fn is_text(head: &[u8]) -> bool {
+    !head.is_empty()
+        && !head.contains(&0)
+        && match std::str::from_utf8(head) {
+            Ok(_) => true,
+            Err(e) => e.error_len().is_none(),
+        }
+}
+
+// 文本类型显式声明 utf-8，避免浏览器按其他编码显示
+fn with_charset(mime: String) -> String {
+    let is_text = mime.starts_with("text/")
+        || matches!(
+            mime.as_str(),
+            "application/json" | "application/javascript" | "application/xml" | "image/svg+xml"
+        );
+    if is_text && !mime.contains("charset=") {
+        format!("{}; charset=utf-8", mime)
+    } else {
+        mime
+    }
+}
+
+fn pick(value: &Option<String>, fallback: &Option<String>) -> Option<String> {
+    non_empty(value).or_else(|| non_empty(fallback))
+}
+
+// 前端输入框留空时会传空字符串，视为未设置
+pub fn non_empty(value: &Option<String>) -> Option<String> {
+    value
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+// put_object 和 create_multipart_upload 的 builder 类型不同但方法同名，用宏统一设置请求头和元数据
+macro_rules! apply_headers {
+    ($request:expr, $headers:expr, $remote_filename:expr, $head:expr) => {{
+        let headers: &$crate::typ::ObjectHeaders = $headers;
+        let mut request = $request
+            .content_type(headers.content_type($remote_filename, $head))
+            .set_cache_control($crate::headers::non_empty(&headers.cache_control))
+            .set_content_disposition($crate::headers::non_empty(&headers.content_disposition))
+            .set_content_encoding($crate::headers::non_empty(&headers.content_encoding))
+            .set_content_language($crate::headers::non_empty(&headers.content_language))
+            .set_expires(headers.expires()?);
+        for (key, value) in headers.metadata() {
+            request = request.metadata(key, value);
+        }
+        request
+    }};
+}
+
+pub(crate) use apply_headers;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(pattern: &str, cache_control: &str) -> HeaderRule {
+        HeaderRule {
+            pattern: pattern.to_string(),
+            headers: ObjectHeaders {
+                cache_control: Some(cache_control.to_string()),
+                ..Default::default()
+            },
+        }
+    }
+
+    #[test]
+    fn pattern_without_slash_matches_file_name() {
+        let wasm = rule("*.wasm", "");
+        assert!(wasm.matches("app.wasm"));
+        assert!(wasm.matches("/static/js/app.wasm"));
+        assert!(!wasm.matches("app.wasm.map"));
+        assert!(!wasm.matches("app.WASM"));
+    }
+
+    #[test]
+    fn pattern_with_slash_matches_full_path() {
+        let assets = rule("assets/**", "");
+        assert!(assets.matches("assets/logo.png"));
+        assert!(assets.matches("/assets/img/logo.png"));
+        assert!(!assets.matches("static/assets/logo.png"));
+        assert!(!assets.matches("logo.png"));
+
+        let top_level = rule("assets/*.css", "");
+        assert!(top_level.matches("assets/site.css"));
+        assert!(!top_level.matches("assets/css/site.css"));
+
+        assert!(!rule("[", "").matches("["));
+    }
+
+    #[test]
+    fn later_rules_win_and_upload_headers_win_over_rules() {
+        let mut all = rule("*", "no-cache");
+        all.headers.content_language = Some("en".to_string());
+        let rules = vec![
+            all,
+            rule("assets/**", "max-age=3600"),
+            rule("*.wasm", "immutable"),
+        ];
+
+        let resolved = resolve(&rules, "assets/app.wasm", &ObjectHeaders::default());
+        assert_eq!(resolved.cache_control.as_deref(), Some("immutable"));
+        assert_eq!(resolved.content_language.as_deref(), Some("en"));
+
+        let resolved = resolve(&rules, "assets/logo.png", &ObjectHeaders::default());
+        assert_eq!(resolved.cache_control.as_deref(), Some("max-age=3600"));
+
+        let resolved = resolve(&rules, "index.html", &ObjectHeaders::default());
+        assert_eq!(resolved.cache_control.as_deref(), Some("no-cache"));
+
+        // 留空的字段不会覆盖规则
+        let upload = ObjectHeaders {
+            cache_control: Some("max-age=60".to_string()),
+            content_language: Some(" ".to_string()),
+            ..Default::default()
+        };
+        let resolved = resolve(&rules, "assets/app.wasm", &upload);
+        assert_eq!(resolved.cache_control.as_deref(), Some("max-age=60"));
+        assert_eq!(resolved.content_language.as_deref(), Some("en"));
+    }
+
+    #[test]
+    fn content_type_falls_back_to_file_extension() {
+        let headers = ObjectHeaders::default();
+        assert_eq!(headers.content_type("fonts/a.woff2", b""), "font/woff2");
+        assert_eq!(
+            headers.content_type("data.bin", b""),
+            "application/octet-stream"
+        );
+
+        let headers = ObjectHeaders {
+            content_type: Some("application/x-custom".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            headers.content_type("page.html", b"<html>"),
+            "application/x-custom"
+        );
+    }
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+    const JPEG: &[u8] = b"\xff\xd8\xff\xe0\0\x10JFIF\0";
+
+    #[test]
+    fn sniff_uses_magic_bytes_without_extension() {
+        assert_eq!(sniff("screenshot", PNG), "image/png");
+        assert_eq!(
+            sniff("notes", "你好".as_bytes()),
+            "text/plain; charset=utf-8"
+        );
+        assert_eq!(sniff("blob", b"\0\x01\x02"), "application/octet-stream");
+        assert_eq!(sniff("empty", b""), "application/octet-stream");
+    }
+
+    #[test]
+    fn sniff_prefers_media_magic_over_wrong_extension() {
+        assert_eq!(sniff("photo.txt", JPEG), "image/jpeg");
+        assert_eq!(sniff("photo.png", JPEG), "image/jpeg");
+        assert_eq!(sniff("photo.jpg", JPEG), "image/jpeg");
+        assert_eq!(sniff("page.html", b"<html>"), "text/html; charset=utf-8");
+    }
+
+    #[test]
+    fn with_charset_only_touches_text_types() {
+        assert_eq!(
+            with_charset("text/css".to_string()),
+            "text/css; charset=utf-8"
+        );
+        assert_eq!(
+            with_charset("application/json".to_string()),
+            "application/json; charset=utf-8"
+        );
+        assert_eq!(
+            with_charset("text/plain; charset=gbk".to_string()),
+            "text/plain; charset=gbk"
+        );
+        assert_eq!(with_charset("image/png".to_string()), "image/png");
+    }
+}
@@ -1,8 +1,23 @@
 use tauri::Manager;
 
+mod atomic_file;
+mod checksum;
+mod connector;
+mod content;
+mod download;
+mod fetch;
+mod headers;
 mod manager;
 mod r2;
+mod registry;
+mod retry;
+mod scheduler;
+mod session;
+mod tasks;
+mod throttle;
+mod tls;
 mod typ;
+mod vault;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -25,12 +40,39 @@ pub fn run() {
     let builder = builder.plugin(tauri_plugin_clipboard::init());
 
     builder
+        .manage(registry::ClientRegistry::default())
+        .manage(tasks::UploadTasks::default())
+        .setup(|app| {
+            app.manage(vault::Vault::open(app.handle()));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             manager::preview_file,
             manager::get_file_details,
             r2::r2_ping,
             r2::r2_upload,
+            r2::r2_list_objects,
+            r2::r2_delete_objects,
+            r2::r2_delete_prefix,
+            r2::r2_copy_objects,
+            r2::r2_copy_prefix,
+            r2::r2_download,
+            r2::r2_presign,
             r2::r2_cancel_upload,
+            r2::r2_pause_upload,
+            r2::r2_resume_upload,
+            r2::r2_set_bandwidth_limit,
+            r2::r2_set_proxy,
+            r2::r2_list_pending_uploads,
+            r2::r2_resume_pending_upload,
+            r2::r2_discard_pending_upload,
+            vault::vault_status,
+            vault::vault_unlock,
+            vault::vault_set_password,
+            vault::vault_reset,
+            vault::vault_set_proxy_password,
+            vault::vault_save_bucket,
+            vault::vault_delete_bucket,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
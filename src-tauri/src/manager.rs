@@ -45,7 +45,7 @@ pub async fn get_file_details(path: String) -> Result<Vec<FileDetail>, String> {
         let base_path = std::path::Path::new(&path)
             .parent()
             .map(|p| p.to_string_lossy().to_string())
-            .unwrap_or_else(|| "".to_string());
+            .unwrap_or_default();
 
         get_file_details_internal(path, &base_path).await
     })
@@ -1,106 +1,737 @@
-use crate::typ::{File, UploadHistory, UploadSource, UploadStatus};
+use crate::checksum;
+use crate::connector;
+use crate::content;
+use crate::download;
+use crate::fetch;
+use crate::headers::{self, apply_headers};
+use crate::registry::ClientRegistry;
+use crate::retry;
+use crate::scheduler::{MAX_IN_FLIGHT_PARTS, SCHEDULER};
+use crate::session;
+use crate::tasks::{MultipartUpload, UploadTasks};
+use crate::throttle;
+use crate::typ::{
+    BandwidthLimit, Bucket, BucketType, Checksum, ConflictPolicy, CopyFailure, CopyItem,
+    CopyProgress, CopyResult, DeleteFailure, DeleteProgress, DeleteResult, DownloadProgress,
+    DownloadState, DownloadStatus, File, FileFingerprint, HeaderRule, Jurisdiction, ListOptions,
+    ObjectEntry, ObjectHeaders, ObjectPage, ObjectSortKey, PendingUpload, PresignMethod,
+    PresignOptions, PresignedUrl, ProxyConfig, RetryConfig, SessionPart, Timeouts, UploadError,
+    UploadHistory, UploadSession, UploadSource, UploadStatus,
+};
+use crate::vault::Vault;
 use aws_config::timeout::TimeoutConfig;
 use aws_config::ConfigLoader;
-use aws_sdk_s3::config::{Credentials, Region};
-use aws_sdk_s3::types::{CompletedMultipartUpload, CompletedPart};
+use aws_sdk_s3::config::{Credentials, Region, RequestChecksumCalculation};
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
+use aws_sdk_s3::operation::head_object::HeadObjectOutput;
+use aws_sdk_s3::operation::list_parts::ListPartsError;
+use aws_sdk_s3::presigning::PresigningConfig;
+use aws_sdk_s3::primitives::ByteStream;
+use aws_sdk_s3::types::{
+    ChecksumMode, CompletedMultipartUpload, CompletedPart, Delete, ObjectIdentifier, Part,
+};
 use aws_sdk_s3::Client;
-use aws_smithy_runtime::client::http::hyper_014::HyperClientBuilder;
-use dashmap::DashMap;
-use hyper::client::HttpConnector;
-use hyper_proxy::ProxyConnector;
-use mime_guess::from_path;
-use once_cell::sync::Lazy;
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::Duration;
+use bytes::Bytes;
+use futures::{StreamExt, TryStreamExt};
+use std::collections::{HashMap, HashSet};
+use std::io::SeekFrom;
+use std::path::Path;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use std::{
     sync::Arc,
     time::{SystemTime, UNIX_EPOCH},
 };
-use tauri::{AppHandle, Emitter};
-use tokio::io::AsyncReadExt;
-use tokio::sync::Semaphore;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::task::JoinSet;
 
-// 键是 file_id，值是一个元组，包含一个 JoinHandle 和一个 Option<String>，用于存储 upload_id，upload_id 用于分段上传
-static UPLOAD_TASKS: Lazy<
-    DashMap<String, (tokio::task::JoinHandle<Result<(), String>>, Option<String>)>,
-> = Lazy::new(DashMap::new);
-
-static UPLOAD_TASKS_INFO: Lazy<DashMap<String, (Arc<R2Client>, String)>> = Lazy::new(DashMap::new);
+const MIB: u64 = 1024 * 1024;
+const MIN_PART_SIZE: u64 = 5 * MIB; // S3/R2 允许的最小分段（最后一段除外）
+const MAX_PART_SIZE: u64 = 5 * 1024 * MIB; // 单个分段最大 5GiB
+const MAX_PARTS: u64 = 10_000; // 单个分段上传最多 10000 段
+const MAX_RENAME_ATTEMPTS: u32 = 1000; // 重命名时最多尝试到 name (1000).ext
+const MAX_LIST_KEYS: i32 = 1000; // ListObjectsV2 单页最多返回 1000 个对象
+const MAX_SEARCH_PAGES: usize = 10; // 单次搜索最多翻 10 页，之后返回 continuation_token 由前端继续
+const HEAD_CONCURRENCY: usize = 16; // 列表页并发 HEAD 获取 Content-Type
+const MAX_DELETE_KEYS: usize = 1000; // DeleteObjects 单次最多删除 1000 个对象
+const MAX_COPY_SIZE: u64 = 5 * 1024 * MIB; // CopyObject 最大 5GiB，更大的对象用 UploadPartCopy
+const COPY_PART_SIZE: u64 = 512 * MIB; // UploadPartCopy 的分段大小
+const COPY_CONCURRENCY: usize = 8; // 同时复制的对象数，以及单个大对象同时复制的分段数
+const DOWNLOAD_CHUNK_SIZE: u64 = 8 * MIB; // 下载时每个范围请求的大小
+const DEFAULT_PRESIGN_EXPIRY_SECS: u64 = 60 * 60; // 预签名 URL 默认 1 小时后过期
+const MAX_PRESIGN_EXPIRY_SECS: u64 = 7 * 24 * 60 * 60; // SigV4 预签名最长 7 天
+const INITIAL_TRANSFER_RATE: u64 = 64 * 1024; // 还没有观测到上传速率时假定的 64KiB/s
+const TRANSFER_TIMEOUT_FACTOR: u32 = 4; // 上传超时取预计耗时的 4 倍
 
 #[tauri::command]
+// 添加或编辑存储桶时测试未保存的配置，bucket 中留空的密钥使用保险库中 bucket_id 已保存的值
 pub async fn r2_ping(
-    bucket_name: &str,
-    account_id: &str,
-    access_key: &str,
-    secret_key: &str,
+    vault: State<'_, Vault>,
+    registry: State<'_, ClientRegistry>,
+    bucket_id: Option<u64>,
+    bucket: Option<Bucket>,
 ) -> Result<(), String> {
-    let client = R2Client::new(bucket_name, account_id, access_key, secret_key, None).await?;
+    // 未保存的配置只用于这一次测试，不放进客户端缓存
+    let (cache_id, bucket) = match (bucket_id, bucket) {
+        (Some(bucket_id), Some(mut bucket)) => {
+            if bucket.access_key.is_empty() || bucket.secret_key.is_empty() {
+                let saved = vault.bucket(bucket_id)?;
+                if bucket.access_key.is_empty() {
+                    bucket.access_key = saved.access_key.clone();
+                }
+                if bucket.secret_key.is_empty() {
+                    bucket.secret_key = saved.secret_key.clone();
+                }
+            }
+            (None, bucket)
+        }
+        (None, Some(bucket)) => (None, bucket),
+        (Some(bucket_id), None) => (Some(bucket_id), vault.bucket(bucket_id)?),
+        (None, None) => return Err("Bucket is required".to_string()),
+    };
+    let client = registry.get(cache_id, &bucket).await?;
     client.ping().await
 }
 
 #[tauri::command]
+#[allow(clippy::too_many_arguments)]
 pub async fn r2_upload(
+    vault: State<'_, Vault>,
+    registry: State<'_, ClientRegistry>,
+    tasks: State<'_, UploadTasks>,
     app: AppHandle,
-    bucket_name: &str,
-    account_id: &str,
-    access_key: &str,
-    secret_key: &str,
-    domain: Option<&str>,
+    bucket_id: u64,
     files: Vec<File>,
+    conflict_policy: Option<ConflictPolicy>,
+    headers: Option<ObjectHeaders>,
 ) -> Result<(), String> {
-    let client =
-        Arc::new(R2Client::new(bucket_name, account_id, access_key, secret_key, domain).await?);
+    let client = registry
+        .get(Some(bucket_id), &vault.bucket(bucket_id)?)
+        .await?;
+    let conflict_policy = conflict_policy.unwrap_or_default();
+    let headers = headers.unwrap_or_default();
 
     for file in files {
         let client = client.clone();
         let app = app.clone();
         let filename = file.remote_filename.clone();
         let file_id = file.id.clone();
+        // 文件级的请求头覆盖批次的请求头
+        let headers = headers.merge(file.headers.as_ref());
+        tasks.register(&file_id);
 
         let handle = tokio::spawn(async move {
-            let result = match &file.source {
-                UploadSource::FilePath(path) => {
-                    client
-                        .stream_upload_file(&app, &path, &filename, &file_id.clone())
-                        .await
-                }
-                UploadSource::FileContent(content) => {
-                    emit_progress(
-                        &app,
-                        format!("{}/{}", client.domain, filename),
-                        file_id.clone(),
-                        filename.clone(),
-                        UploadStatus::Uploading {
-                            progress: 0.0,
-                            bytes_uploaded: 0,
-                            total_bytes: content.len() as u64,
-                            speed: 0.0,
+            emit_progress(
+                &app,
+                format!("{}/{}", client.domain, filename),
+                file_id.clone(),
+                filename.clone(),
+                UploadStatus::Queued,
+            );
+
+            let result = async {
+                // 等待调度器放行，超出并发文件数的上传保持 queued 状态
+                acquire_file_permit(&app, &file_id).await?;
+                wait_while_paused(
+                    &app,
+                    format!("{}/{}", client.domain, filename),
+                    &file_id,
+                    &filename,
+                    0,
+                    0,
+                )
+                .await?;
+
+                // 内存中的内容只解码一次，冲突检查和上传共用
+                let content = content::decode(&file.source)?;
+                let local = match (&file.source, &content) {
+                    (UploadSource::FilePath(path), _) => Some(Local::Path(path)),
+                    (_, Some(content)) => Some(Local::Bytes(&content.bytes)),
+                    _ => None,
+                };
+
+                // 远端已存在同名对象时按冲突策略处理
+                let filename = match client
+                    .resolve_conflict(conflict_policy, local, &filename)
+                    .await?
+                {
+                    Conflict::Upload(filename) => filename,
+                    Conflict::Skip(reason) => return Ok(UploadOutcome::Skipped(reason)),
+                };
+
+                match (&file.source, content) {
+                    (UploadSource::FilePath(path), _) => {
+                        client
+                            .stream_upload_file(&app, path, &filename, &file_id.clone(), &headers)
+                            .await?
+                    }
+                    (_, Some(content)) => {
+                        // data URL 头部的类型优先于存储桶规则，但不覆盖显式指定的类型
+                        let headers = ObjectHeaders {
+                            content_type: content.content_type,
+                            ..Default::default()
+                        }
+                        .merge(Some(&headers));
+                        client
+                            .upload_bytes(&app, content.bytes, &filename, &file_id, &headers)
+                            .await?
+                    }
+                    (
+                        UploadSource::Url {
+                            url,
+                            headers: request_headers,
+                            max_bytes,
                         },
-                    );
-                    client.upload_content(content, &filename).await
+                        _,
+                    ) => {
+                        client
+                            .stream_upload_url(
+                                &app,
+                                url,
+                                request_headers,
+                                *max_bytes,
+                                &filename,
+                                &file_id,
+                                &headers,
+                            )
+                            .await?
+                    }
+                    (_, None) => return Err("Unsupported upload source".to_string().into()),
                 }
+                Ok::<_, UploadError>(UploadOutcome::Uploaded(filename))
+            }
+            .await;
+
+            app.state::<UploadTasks>().finish(&file_id);
+            emit_result(&app, &client, file_id, filename, &result);
+            result.map(|_| ())
+        });
+
+        tasks.set_handle(&file.id, handle);
+    }
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn r2_list_objects(
+    vault: State<'_, Vault>,
+    registry: State<'_, ClientRegistry>,
+    bucket_id: u64,
+    options: Option<ListOptions>,
+) -> Result<ObjectPage, String> {
+    let client = registry
+        .get(Some(bucket_id), &vault.bucket(bucket_id)?)
+        .await?;
+    client.list_objects(options.unwrap_or_default()).await
+}
+
+// 按 1000 个一批调用 DeleteObjects，单批失败时该批所有 key 记为失败，继续删除后面的批次
+#[tauri::command]
+pub async fn r2_delete_objects(
+    vault: State<'_, Vault>,
+    registry: State<'_, ClientRegistry>,
+    app: AppHandle,
+    bucket_id: u64,
+    task_id: String,
+    keys: Vec<String>,
+) -> Result<DeleteResult, String> {
+    let client = registry
+        .get(Some(bucket_id), &vault.bucket(bucket_id)?)
+        .await?;
+    let mut result = DeleteResult::default();
+    let total = Some(keys.len() as u64);
+
+    for batch in keys.chunks(MAX_DELETE_KEYS) {
+        client.delete_batch(batch, &mut result).await;
+        emit_delete_progress(&app, &task_id, &result, total, false);
+    }
+
+    emit_delete_progress(&app, &task_id, &result, total, true);
+    Ok(result)
+}
+
+// 递归删除前缀下的所有对象，边列边删；空前缀会删除整个存储桶，直接拒绝
+// 前缀按文件夹处理，删除 photos 不会误删 photos-old/ 下的对象
+#[tauri::command]
+pub async fn r2_delete_prefix(
+    vault: State<'_, Vault>,
+    registry: State<'_, ClientRegistry>,
+    app: AppHandle,
+    bucket_id: u64,
+    task_id: String,
+    prefix: String,
+) -> Result<DeleteResult, String> {
+    if prefix.is_empty() {
+        return Err("Refusing to delete an empty prefix".to_string());
+    }
+    let prefix = folder_prefix(&prefix);
+    let client = registry
+        .get(Some(bucket_id), &vault.bucket(bucket_id)?)
+        .await?;
+    let mut result = DeleteResult::default();
+    let mut continuation_token = None;
+
+    loop {
+        let output = client
+            .client
+            .list_objects_v2()
+            .bucket(&client.bucket_name)
+            .prefix(&prefix)
+            .max_keys(MAX_DELETE_KEYS as i32)
+            .set_continuation_token(continuation_token.take())
+            .send()
+            .await
+            .map_err(|e| e.to_string())?;
+        let keys: Vec<String> = output
+            .contents()
+            .iter()
+            .filter_map(|object| object.key().map(str::to_string))
+            .collect();
+        if !keys.is_empty() {
+            client.delete_batch(&keys, &mut result).await;
+            emit_delete_progress(&app, &task_id, &result, None, false);
+        }
+
+        match output.next_continuation_token() {
+            Some(token) if output.is_truncated().unwrap_or(false) => {
+                continuation_token = Some(token.to_string());
+            }
+            _ => break,
+        }
+    }
+
+    emit_delete_progress(&app, &task_id, &result, None, true);
+    Ok(result)
+}
+
+// 在同一账户内复制对象，target_bucket 为空时复制到当前存储桶；delete_source 为 true 时为移动
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn r2_copy_objects(
+    vault: State<'_, Vault>,
+    registry: State<'_, ClientRegistry>,
+    app: AppHandle,
+    bucket_id: u64,
+    task_id: String,
+    items: Vec<CopyItem>,
+    target_bucket: Option<String>,
+    delete_source: Option<bool>,
+) -> Result<CopyResult, String> {
+    let client = registry
+        .get(Some(bucket_id), &vault.bucket(bucket_id)?)
+        .await?;
+    client
+        .copy_items(
+            &app,
+            &task_id,
+            items,
+            target_bucket,
+            delete_source.unwrap_or(false),
+        )
+        .await
+}
+
+// 复制或移动整个前缀，source_prefix 下的 key 替换前缀后写到 target_prefix 下
+// 复制和移动都和删除前缀一样按文件夹处理，避免带上前缀相同的其他对象
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn r2_copy_prefix(
+    vault: State<'_, Vault>,
+    registry: State<'_, ClientRegistry>,
+    app: AppHandle,
+    bucket_id: u64,
+    task_id: String,
+    source_prefix: String,
+    target_prefix: String,
+    target_bucket: Option<String>,
+    delete_source: Option<bool>,
+) -> Result<CopyResult, String> {
+    let delete_source = delete_source.unwrap_or(false);
+    if delete_source && source_prefix.is_empty() {
+        return Err("Refusing to move an empty prefix".to_string());
+    }
+    let source_prefix = folder_prefix_or_root(&source_prefix);
+    let target_prefix = folder_prefix_or_root(&target_prefix);
+    let client = registry
+        .get(Some(bucket_id), &vault.bucket(bucket_id)?)
+        .await?;
+    // 同一存储桶内前缀互相包含时，复制顺序不定，可能先覆盖还没读取的源对象
+    let same_bucket = target_bucket
+        .as_deref()
+        .filter(|b| !b.is_empty())
+        .is_none_or(|b| b == client.bucket_name);
+    if same_bucket && prefixes_overlap(&source_prefix, &target_prefix) {
+        return Err(format!(
+            "Source prefix {} and target prefix {} overlap",
+            source_prefix, target_prefix
+        ));
+    }
+    // 先列出全部 key 再复制，避免目标前缀位于源前缀之下时把新复制的对象也列进来
+    let items = client
+        .list_keys(&source_prefix)
+        .await?
+        .into_iter()
+        .map(|key| CopyItem {
+            target_key: format!("{}{}", target_prefix, &key[source_prefix.len()..]),
+            source_key: key,
+        })
+        .collect();
+    client
+        .copy_items(&app, &task_id, items, target_bucket, delete_source)
+        .await
+}
+
+// 补上末尾的 /，让前缀只匹配这个文件夹下的对象
+fn folder_prefix(prefix: &str) -> String {
+    if prefix.ends_with('/') {
+        prefix.to_string()
+    } else {
+        format!("{}/", prefix)
+    }
+}
+
+// 空前缀表示整个存储桶，保持为空
+fn folder_prefix_or_root(prefix: &str) -> String {
+    if prefix.is_empty() {
+        String::new()
+    } else {
+        folder_prefix(prefix)
+    }
+}
+
+fn prefixes_overlap(a: &str, b: &str) -> bool {
+    a.starts_with(b) || b.starts_with(a)
+}
+
+// 同一存储桶内目标 key 同时也是另一项的源 key 时，返回第一个这样的 key
+// 源和目标相同的项由 copy_object 单独报错
+fn target_overwrites_source(items: &[CopyItem]) -> Option<&str> {
+    let sources: HashSet<&str> = items.iter().map(|item| item.source_key.as_str()).collect();
+    items
+        .iter()
+        .filter(|item| item.target_key != item.source_key)
+        .map(|item| item.target_key.as_str())
+        .find(|key| sources.contains(key))
+}
+
+// 生成限时的 GET（分享已有对象）或 PUT（让别人上传到指定 key）URL
+#[tauri::command]
+pub async fn r2_presign(
+    vault: State<'_, Vault>,
+    registry: State<'_, ClientRegistry>,
+    bucket_id: u64,
+    key: String,
+    options: Option<PresignOptions>,
+) -> Result<PresignedUrl, String> {
+    let client = registry
+        .get(Some(bucket_id), &vault.bucket(bucket_id)?)
+        .await?;
+    client.presign(&key, options.unwrap_or_default()).await
+}
+
+// 下载对象或整个前缀到本地目录：keys 直接保存为目标目录下的同名文件，同名时整批报错，
+// prefix 下的对象保留前缀最后一级目录之后的结构，例如 assets/img/ 下的 a.png 保存为 img/a.png
+#[tauri::command]
+#[allow(clippy::too_many_arguments)]
+pub async fn r2_download(
+    vault: State<'_, Vault>,
+    registry: State<'_, ClientRegistry>,
+    app: AppHandle,
+    bucket_id: u64,
+    task_id: String,
+    keys: Option<Vec<String>>,
+    prefix: Option<String>,
+    target_dir: String,
+) -> Result<(), String> {
+    let client = registry
+        .get(Some(bucket_id), &vault.bucket(bucket_id)?)
+        .await?;
+
+    let mut items = Vec::new();
+    // 和按前缀下载一样跳过控制台创建的“文件夹”占位对象
+    for key in keys.unwrap_or_default() {
+        if key.ends_with('/') {
+            continue;
+        }
+        let name = key.rsplit('/').next().unwrap_or(&key);
+        items.push((download::local_path(&target_dir, name)?, key));
+    }
+    if let Some(prefix) = prefix.filter(|p| !p.is_empty()) {
+        let base = prefix
+            .trim_end_matches('/')
+            .rsplit_once('/')
+            .map_or(0, |(parent, _)| parent.len() + 1);
+        for key in client.list_keys(&prefix).await? {
+            // 跳过控制台创建的“文件夹”占位对象
+            if key.ends_with('/') {
+                continue;
+            }
+            items.push((download::local_path(&target_dir, &key[base..])?, key));
+        }
+    }
+
+    // 同一个 key 只下载一次；不同的 key 落到同一个本地文件时（例如同时选中 a/x.png 和 b/x.png）
+    // 在开始下载前报错，避免后下载的文件覆盖先下载的
+    let mut seen_keys = HashSet::new();
+    items.retain(|(_, key)| seen_keys.insert(key.clone()));
+    let mut seen_paths = HashMap::new();
+    for (path, key) in &items {
+        if let Some(other) = seen_paths.insert(path, key) {
+            return Err(format!(
+                "Objects {} and {} would both be downloaded to {}",
+                other,
+                key,
+                path.display()
+            ));
+        }
+    }
+
+    for (path, key) in items {
+        let client = client.clone();
+        let app = app.clone();
+        let task_id = task_id.clone();
+        emit_download_progress(&app, &task_id, &key, &path, DownloadStatus::Queued);
+
+        tokio::spawn(async move {
+            let result = async {
+                // 和上传共用调度器，限制同时传输的文件数
+                let _permit = SCHEDULER.acquire_file().await?;
+                client.download_object(&app, &task_id, &key, &path).await
+            }
+            .await;
+
+            let status = match result {
+                Ok(()) => DownloadStatus::Success,
+                Err(message) => DownloadStatus::Error { message },
             };
+            emit_download_progress(&app, &task_id, &key, &path, status);
+        });
+    }
+
+    Ok(())
+}
+
+fn emit_download_progress(
+    app: &AppHandle,
+    task_id: &str,
+    key: &str,
+    path: &Path,
+    status: DownloadStatus,
+) {
+    let _ = app.emit(
+        "download-progress",
+        DownloadProgress {
+            task_id: task_id.to_string(),
+            key: key.to_string(),
+            path: path.to_string_lossy().to_string(),
+            status,
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        },
+    );
+}
+
+fn emit_copy_progress(app: &AppHandle, task_id: &str, result: &CopyResult, total: u64, done: bool) {
+    let _ = app.emit(
+        "copy-progress",
+        CopyProgress {
+            task_id: task_id.to_string(),
+            copied: result.copied,
+            failed: result.failed.len() as u64,
+            total,
+            done,
+        },
+    );
+}
+
+fn emit_delete_progress(
+    app: &AppHandle,
+    task_id: &str,
+    result: &DeleteResult,
+    total: Option<u64>,
+    done: bool,
+) {
+    let _ = app.emit(
+        "delete-progress",
+        DeleteProgress {
+            task_id: task_id.to_string(),
+            deleted: result.deleted,
+            failed: result.failed.len() as u64,
+            total,
+            done,
+        },
+    );
+}
+
+// 列出磁盘上尚未完成的分段上传会话，供前端在启动时提示续传
+// 按存储桶名和 endpoint 找回会话所属的存储桶，前端用这个 id 续传
+#[tauri::command]
+pub async fn r2_list_pending_uploads(
+    vault: State<'_, Vault>,
+    app: AppHandle,
+) -> Result<Vec<PendingUpload>, String> {
+    let sessions = session::load_all(&app).await?;
+    Ok(sessions
+        .into_iter()
+        .map(|session| {
+            let bucket_id = vault.find_bucket(|bucket| {
+                bucket.bucket_name == session.bucket_name
+                    && endpoint_url(bucket).ok().flatten().unwrap_or_default() == session.endpoint
+            });
+            PendingUpload { bucket_id, session }
+        })
+        .collect())
+}
+
+#[tauri::command]
+pub async fn r2_resume_pending_upload(
+    vault: State<'_, Vault>,
+    registry: State<'_, ClientRegistry>,
+    tasks: State<'_, UploadTasks>,
+    app: AppHandle,
+    bucket_id: u64,
+    file_id: String,
+) -> Result<(), String> {
+    let upload_session = session::load(&app, &file_id)
+        .await?
+        .ok_or_else(|| "Upload session not found".to_string())?;
+    let client = registry
+        .get(Some(bucket_id), &vault.bucket(bucket_id)?)
+        .await?;
+    if upload_session.bucket_name != client.bucket_name
+        || upload_session.endpoint != client.endpoint
+    {
+        return Err("Upload session belongs to a different bucket".to_string());
+    }
 
+    let filename = upload_session.remote_filename.clone();
+    tasks.register(&file_id);
+    let handle = {
+        let app = app.clone();
+        let file_id = file_id.clone();
+        tokio::spawn(async move {
             emit_progress(
                 &app,
                 format!("{}/{}", client.domain, filename),
-                file_id,
-                filename,
-                match &result {
-                    Ok(_) => UploadStatus::Success,
-                    Err(e) => UploadStatus::Error {
-                        message: e.to_string(),
-                        code: "UPLOAD_ERROR".to_string(),
-                    },
-                },
+                file_id.clone(),
+                filename.clone(),
+                UploadStatus::Queued,
             );
 
-            result
-        });
+            let result = async {
+                acquire_file_permit(&app, &file_id).await?;
+                client.resume_session(&app, upload_session).await?;
+                Ok::<_, UploadError>(UploadOutcome::Uploaded(filename.clone()))
+            }
+            .await;
+            app.state::<UploadTasks>().finish(&file_id);
+            emit_result(&app, &client, file_id, filename, &result);
+            result.map(|_| ())
+        })
+    };
+
+    tasks.set_handle(&file_id, handle);
+    Ok(())
+}
+
+// 放弃一个未完成的会话：中止服务端的分段上传并删除本地记录
+// 存储桶已被删除时 bucket_id 为空，只删除本地记录
+#[tauri::command]
+pub async fn r2_discard_pending_upload(
+    vault: State<'_, Vault>,
+    registry: State<'_, ClientRegistry>,
+    app: AppHandle,
+    bucket_id: Option<u64>,
+    file_id: String,
+) -> Result<(), String> {
+    if let (Some(bucket_id), Some(upload_session)) =
+        (bucket_id, session::load(&app, &file_id).await?)
+    {
+        let client = registry
+            .get(Some(bucket_id), &vault.bucket(bucket_id)?)
+            .await?;
+        let _ = client
+            .abort_multipart_upload(&upload_session.remote_filename, &upload_session.upload_id)
+            .await;
+    }
+    session::remove(&app, &file_id).await
+}
+
+// 暂停正在上传或排队的文件：已在上传的分段会继续完成，之后不再读取新的分段，分段上传会话保留
+#[tauri::command]
+pub async fn r2_pause_upload(
+    tasks: State<'_, UploadTasks>,
+    file_ids: Vec<String>,
+) -> Result<(), String> {
+    for file_id in file_ids {
+        tasks.set_paused(&file_id, true);
+    }
+    Ok(())
+}
 
-        UPLOAD_TASKS.insert(file.id.clone(), (handle, None));
+#[tauri::command]
+pub async fn r2_resume_upload(
+    tasks: State<'_, UploadTasks>,
+    file_ids: Vec<String>,
+) -> Result<(), String> {
+    for file_id in file_ids {
+        tasks.set_paused(&file_id, false);
+    }
+    Ok(())
+}
+
+// 获取调度器的文件许可交给上传控制保管，上传结束移除控制时一起归还
+async fn acquire_file_permit(app: &AppHandle, file_id: &str) -> Result<(), String> {
+    let permit = SCHEDULER.acquire_file().await?;
+    if let Some(control) = app.state::<UploadTasks>().control(file_id) {
+        *control.permit.lock().unwrap() = Some(permit);
+    }
+    Ok(())
+}
+
+// 文件处于暂停状态时报告 Paused 并在这里等待，直到 r2_resume_upload 解除暂停
+async fn wait_while_paused(
+    app: &AppHandle,
+    url: String,
+    file_id: &str,
+    filename: &str,
+    bytes_uploaded: u64,
+    total_bytes: u64,
+) -> Result<(), String> {
+    let Some(control) = app.state::<UploadTasks>().control(file_id) else {
+        return Ok(());
+    };
+    let mut paused = control.paused.subscribe();
+    if !*paused.borrow() {
+        return Ok(());
     }
 
+    emit_progress(
+        app,
+        url,
+        file_id.to_string(),
+        filename.to_string(),
+        UploadStatus::Paused {
+            progress: if total_bytes > 0 {
+                bytes_uploaded as f64 / total_bytes as f64
+            } else {
+                0.0
+            },
+            bytes_uploaded,
+            total_bytes,
+        },
+    );
+    // 暂停期间归还文件许可，恢复后重新排队获取
+    let had_permit = control.permit.lock().unwrap().take().is_some();
+    let _ = paused.wait_for(|paused| !*paused).await;
+    if had_permit {
+        let permit = SCHEDULER.acquire_file().await?;
+        *control.permit.lock().unwrap() = Some(permit);
+    }
     Ok(())
 }
 
@@ -126,134 +757,376 @@ pub fn emit_progress(
     );
 }
 
+fn emit_result(
+    app: &AppHandle,
+    client: &R2Client,
+    file_id: String,
+    filename: String,
+    result: &Result<UploadOutcome, UploadError>,
+) {
+    // 重命名上传时以实际的远端文件名报告
+    let filename = match result {
+        Ok(UploadOutcome::Uploaded(remote_filename)) => remote_filename.clone(),
+        _ => filename,
+    };
+    emit_progress(
+        app,
+        format!("{}/{}", client.domain, filename),
+        file_id,
+        filename,
+        match result {
+            Ok(UploadOutcome::Uploaded(_)) => UploadStatus::Success,
+            Ok(UploadOutcome::Skipped(reason)) => UploadStatus::Skipped {
+                reason: reason.to_string(),
+            },
+            Err(e) => UploadStatus::Error {
+                message: e.message.clone(),
+                code: e.code.to_string(),
+            },
+        },
+    );
+}
+
+// 设置全局限速，对所有存储桶的上传生效
+#[tauri::command]
+pub async fn r2_set_bandwidth_limit(limit: BandwidthLimit) -> Result<(), String> {
+    throttle::set_global_limit(limit);
+    Ok(())
+}
+
+// 设置代理，之后新建的客户端生效
 #[tauri::command]
-pub async fn r2_cancel_upload(app: AppHandle, file_id: String) -> Result<(), String> {
-    // First get all the information we need
-    let task_info = UPLOAD_TASKS
-        .get(&file_id)
-        .map(|entry| (entry.0.abort(), entry.1.clone()));
+pub async fn r2_set_proxy(proxy: ProxyConfig) -> Result<(), String> {
+    connector::set_proxy(proxy)
+}
 
-    let client_info = UPLOAD_TASKS_INFO
-        .get(&file_id)
-        .map(|entry| (entry.0.clone(), entry.1.clone()));
+#[tauri::command]
+pub async fn r2_cancel_upload(
+    tasks: State<'_, UploadTasks>,
+    app: AppHandle,
+    file_id: String,
+) -> Result<(), String> {
+    // First abort the task
+    let Some(multipart) = tasks.cancel(&file_id) else {
+        return Ok(());
+    };
 
+    // Then abort the multipart upload if there is one
     let mut filename = "".to_string();
+    if let Some(upload) = multipart {
+        let _ = upload
+            .client
+            .abort_multipart_upload(&upload.remote_filename, &upload.upload_id)
+            .await;
+        filename = upload.remote_filename;
+    }
+    session::remove(&app, &file_id).await?;
 
-    // Then handle the cancellation
-    if let Some((_, upload_id)) = task_info {
-        if let Some(upload_id) = upload_id {
-            if let Some((client, remote_filename)) = client_info {
-                filename = remote_filename.clone();
-                let _ = client
-                    .abort_multipart_upload(&remote_filename, &upload_id)
-                    .await;
-            }
-        }
+    // emit
+    emit_progress(
+        &app,
+        "".to_string(),
+        file_id,
+        filename,
+        UploadStatus::Cancelled,
+    );
 
-        // Finally remove the entries
-        UPLOAD_TASKS.remove(&file_id);
-        UPLOAD_TASKS_INFO.remove(&file_id);
+    Ok(())
+}
 
-        // emit
-        emit_progress(
-            &app,
-            "".to_string(),
-            file_id,
-            filename,
-            UploadStatus::Cancelled,
-        );
+// 单个文件上传任务的结果，Uploaded 中是实际使用的远端文件名
+enum UploadOutcome {
+    Uploaded(String),
+    Skipped(&'static str),
+}
+
+enum Conflict {
+    Upload(String),
+    Skip(&'static str),
+}
+
+// 用于和远端对象比较的本地内容
+#[derive(Clone, Copy)]
+enum Local<'a> {
+    Path(&'a str),
+    Bytes(&'a [u8]),
+}
+
+// 分段数据的来源，内存中的内容没有会话文件，无法在重启后续传
+enum PartSource {
+    File(tokio::fs::File),
+    Memory(Arc<Vec<u8>>),
+    // 只能顺序读取，offset 仅作参考
+    Stream(fetch::Download),
+}
+
+impl PartSource {
+    fn is_persistent(&self) -> bool {
+        matches!(self, PartSource::File(_))
     }
 
-    Ok(())
+    fn is_size_known(&self) -> bool {
+        match self {
+            PartSource::Stream(download) => download.content_length().is_some(),
+            _ => true,
+        }
+    }
+
+    async fn read(&mut self, offset: usize, len: usize) -> Result<Vec<u8>, String> {
+        match self {
+            PartSource::File(file) => {
+                let mut buffer = vec![0; len];
+                file.seek(SeekFrom::Start(offset as u64))
+                    .await
+                    .map_err(|e| e.to_string())?;
+                file.read_exact(&mut buffer)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                Ok(buffer)
+            }
+            PartSource::Memory(bytes) => Ok(bytes[offset..offset + len].to_vec()),
+            PartSource::Stream(download) => download.read(len).await,
+        }
+    }
 }
 
 #[derive(Clone)]
 pub struct R2Client {
     client: Client,
     bucket_name: String,
+    endpoint: String,
     domain: String,
+    retry: RetryConfig,
+    part_size: Option<u64>,
+    bandwidth_limit: Option<BandwidthLimit>,
+    checksum: Checksum,
+    header_rules: Vec<HeaderRule>,
+    timeouts: Timeouts,
+    // 单次上传请求观测到的速率（字节/秒）的滑动平均，0 表示还没有观测值
+    transfer_rate: Arc<AtomicU64>,
 }
 
 impl R2Client {
-    pub async fn new(
-        bucket_name: &str,
-        account_id: &str,
-        access_key: &str,
-        secret_key: &str,
-        domain: Option<&str>,
-    ) -> Result<Self, String> {
+    pub async fn new(bucket: &Bucket, proxy: &ProxyConfig) -> Result<Self, String> {
         println!("new r2 client...");
-        // 设置环境变量 AWS_REQUEST_CHECKSUM_CALCULATION
-        std::env::set_var("AWS_REQUEST_CHECKSUM_CALCULATION", "WHEN_REQUIRED");
-
-        let credentials = Credentials::new(access_key, secret_key, None, None, "R2Uploader");
 
-        // 设置超时配置
-        let timeout_config = TimeoutConfig::builder()
-            .connect_timeout(Duration::from_secs(30)) // 连接超时 30 秒
-            .read_timeout(Duration::from_secs(30)) // 读取超时 30 秒
-            .build();
+        let credentials = Credentials::new(
+            &bucket.access_key,
+            &bucket.secret_key,
+            None,
+            None,
+            "R2Uploader",
+        );
 
         let mut config_loader = ConfigLoader::default()
-            .region(Region::new("auto"))
-            .endpoint_url(format!("https://{}.r2.cloudflarestorage.com", account_id))
-            .timeout_config(timeout_config)
+            .region(Region::new(region(bucket)))
+            .timeout_config(timeout_config(&bucket.timeouts))
             .credentials_provider(credentials);
 
-        if let Some(proxy_connector) = create_proxy_connector() {
-            config_loader =
-                config_loader.http_client(HyperClientBuilder::new().build(proxy_connector));
+        // 未指定 endpoint 的 s3 存储桶交给 SDK 解析为 AWS S3 的默认地址
+        let endpoint = endpoint_url(bucket)?;
+        if let Some(endpoint) = &endpoint {
+            config_loader = config_loader.endpoint_url(endpoint);
+        }
+
+        if let Some(http_client) = connector::http_client(proxy, &bucket.tls)? {
+            config_loader = config_loader.http_client(http_client);
         }
 
         let config = config_loader.load().await;
+        let s3_config = aws_sdk_s3::config::Builder::from(&config)
+            .force_path_style(bucket.force_path_style)
+            // 不让 SDK 自动附加 CRC32，部分 S3 兼容服务不支持；需要校验时由 checksum 配置显式计算
+            .request_checksum_calculation(RequestChecksumCalculation::WhenRequired)
+            .build();
 
         Ok(Self {
-            client: Client::new(&config),
-            bucket_name: bucket_name.to_string(),
-            domain: domain.unwrap_or("").to_string(),
+            client: Client::from_conf(s3_config),
+            bucket_name: bucket.bucket_name.clone(),
+            endpoint: endpoint.unwrap_or_default(),
+            domain: bucket.custom_domain.clone().unwrap_or_default(),
+            retry: bucket.retry,
+            part_size: bucket.part_size_mb.map(|mb| mb * MIB),
+            bandwidth_limit: bucket.bandwidth_limit.clone(),
+            checksum: bucket.checksum,
+            header_rules: bucket.header_rules.clone(),
+            timeouts: bucket.timeouts,
+            transfer_rate: Arc::new(AtomicU64::new(0)),
         })
     }
 
-    // 上传文件内容，一般是文字或图片，内容不会太大，直接上传，且不需要进度
-    pub async fn upload_content(&self, content: &str, remote_filename: &str) -> Result<(), String> {
-        self.client
-            .put_object()
-            .bucket(&self.bucket_name)
-            .key(remote_filename)
-            .body(content.as_bytes().to_vec().into())
-            .content_type(
-                from_path(remote_filename)
-                    .first_or_octet_stream()
-                    .to_string(),
-            )
-            .send()
-            .await
-            .map_err(|e| e.to_string())?;
-        Ok(())
+    // 上传 len 字节的单次请求超时，read 超时设为不限制时返回 None
+    fn transfer_timeout(&self, len: usize) -> Option<Duration> {
+        transfer_timeout(
+            len,
+            self.transfer_rate.load(Ordering::Relaxed),
+            throttle::current_rate(self.bandwidth_limit.as_ref()),
+            self.timeouts.read_secs,
+        )
     }
 
-    // 创建多部分上传
-    async fn create_multipart_upload(&self, remote_filename: &str) -> Result<String, String> {
-        self.client
-            .create_multipart_upload()
-            .bucket(&self.bucket_name)
-            .key(remote_filename)
-            .content_type(from_path(remote_filename).first_or_octet_stream().as_ref())
-            .send()
-            .await
-            .map_err(|e| e.to_string())?
-            .upload_id()
-            .ok_or_else(|| "Failed to get upload ID".to_string())
-            .map(|id| id.to_string())
+    // 上传成功后更新速率，新样本占 1/4 权重，避免个别慢请求导致超时骤变
+    fn record_transfer(&self, len: usize, elapsed: Duration) {
+        let sample = (len as f64 / elapsed.as_secs_f64().max(0.001)) as u64;
+        let rate = match self.transfer_rate.load(Ordering::Relaxed) {
+            0 => sample,
+            rate => (rate * 3 + sample) / 4,
+        };
+        self.transfer_rate.store(rate.max(1), Ordering::Relaxed);
     }
 
-    async fn complete_multipart_upload(
+    // 上传请求的超时设置：read 和单次尝试都放宽到按大小估算的时间，read 不限制时两者都不限制
+    fn transfer_config(&self, len: usize) -> aws_sdk_s3::config::Builder {
+        let mut builder = TimeoutConfig::builder();
+        builder.set_connect_timeout(self.timeouts.connect_secs.map(Duration::from_secs));
+        let timeout_config = match self.transfer_timeout(len) {
+            Some(timeout) => builder
+                .read_timeout(timeout)
+                .operation_attempt_timeout(timeout),
+            None => builder
+                .disable_read_timeout()
+                .disable_operation_attempt_timeout(),
+        }
+        .build();
+        aws_sdk_s3::config::Builder::default().timeout_config(timeout_config)
+    }
+
+    // 上传内存中的内容，一般是文字或粘贴的图片；超过最小分段大小时走分段上传并报告进度
+    // 内存中的内容无法在重启后续传，因此不写会话文件
+    async fn upload_bytes(
         &self,
+        app: &tauri::AppHandle,
+        bytes: Vec<u8>,
         remote_filename: &str,
-        upload_id: &str,
-        parts: Vec<CompletedPart>,
-    ) -> Result<(), String> {
-        self.client
+        file_id: &str,
+        headers: &ObjectHeaders,
+    ) -> Result<(), UploadError> {
+        let size = bytes.len() as u64;
+        emit_progress(
+            app,
+            format!("{}/{}", self.domain, remote_filename),
+            file_id.to_string(),
+            remote_filename.to_string(),
+            UploadStatus::Uploading {
+                progress: 0.0,
+                bytes_uploaded: 0,
+                total_bytes: size,
+                speed: 0.0,
+            },
+        );
+
+        if size < MIN_PART_SIZE {
+            return self.put_object(remote_filename, bytes, headers).await;
+        }
+
+        let part_size = part_size_for(size, self.part_size)?;
+        let file_checksum = checksum::digest(self.checksum, &bytes);
+        let upload_id = self
+            .create_multipart_upload(
+                remote_filename,
+                file_checksum.as_deref(),
+                headers,
+                &bytes[..bytes.len().min(headers::SNIFF_LEN)],
+            )
+            .await?;
+        let upload_session = self.new_session(
+            file_id,
+            String::new(),
+            remote_filename,
+            upload_id,
+            part_size,
+            file_checksum,
+            FileFingerprint { size, modified: 0 },
+        );
+
+        self.upload_parts(app, upload_session, PartSource::Memory(Arc::new(bytes)))
+            .await
+    }
+
+    // 单次 PUT 上传，开启校验和时附带整个对象的校验和并写入元数据，完成后校验
+    async fn put_object(
+        &self,
+        remote_filename: &str,
+        body: Vec<u8>,
+        headers: &ObjectHeaders,
+    ) -> Result<(), UploadError> {
+        let size = body.len() as u64;
+        let checksum = checksum::digest(self.checksum, &body);
+        let mut request = apply_headers!(
+            self.client
+                .put_object()
+                .bucket(&self.bucket_name)
+                .key(remote_filename),
+            &headers::resolve(&self.header_rules, remote_filename, headers),
+            remote_filename,
+            &body[..body.len().min(headers::SNIFF_LEN)]
+        );
+        if let Some(checksum) = &checksum {
+            request = request.metadata(checksum::metadata_key(self.checksum), checksum);
+            request = match self.checksum {
+                Checksum::Sha256 => request.checksum_sha256(checksum),
+                Checksum::Crc32c => request.checksum_crc32_c(checksum),
+                Checksum::None => request,
+            };
+        }
+
+        let started = Instant::now();
+        request
+            .body(self.body(body.into()))
+            .customize()
+            .config_override(self.transfer_config(size as usize))
+            .send()
+            .await
+            .map_err(|e| match e.code() {
+                Some("BadDigest") => {
+                    UploadError::new(UploadError::CHECKSUM_MISMATCH, e.to_string())
+                }
+                _ => UploadError::from(e.to_string()),
+            })?;
+        self.record_transfer(size as usize, started.elapsed());
+
+        self.verify_object(remote_filename, size, self.checksum, checksum.as_deref())
+            .await
+    }
+
+    // 创建多部分上传
+    async fn create_multipart_upload(
+        &self,
+        remote_filename: &str,
+        file_checksum: Option<&str>,
+        headers: &ObjectHeaders,
+        head: &[u8],
+    ) -> Result<String, String> {
+        let mut request = apply_headers!(
+            self.client
+                .create_multipart_upload()
+                .bucket(&self.bucket_name)
+                .key(remote_filename)
+                .set_checksum_algorithm(checksum::algorithm(self.checksum)),
+            &headers::resolve(&self.header_rules, remote_filename, headers),
+            remote_filename,
+            head
+        );
+        if let Some(file_checksum) = file_checksum {
+            request = request.metadata(checksum::metadata_key(self.checksum), file_checksum);
+        }
+
+        request
+            .send()
+            .await
+            .map_err(|e| e.to_string())?
+            .upload_id()
+            .ok_or_else(|| "Failed to get upload ID".to_string())
+            .map(|id| id.to_string())
+    }
+
+    async fn complete_multipart_upload(
+        &self,
+        remote_filename: &str,
+        upload_id: &str,
+        parts: Vec<CompletedPart>,
+    ) -> Result<(), String> {
+        self.client
             .complete_multipart_upload()
             .bucket(&self.bucket_name)
             .key(remote_filename)
@@ -266,7 +1139,7 @@ impl R2Client {
             .send()
             .await
             .map_err(|e| {
-                println!("完成多部分上传时遇到错误：{}", e.to_string());
+                println!("完成多部分上传时遇到错误：{}", e);
                 e.to_string()
             })?;
         Ok(())
@@ -278,25 +1151,77 @@ impl R2Client {
         upload_id: &str,
         part_number: i32,
         body: Vec<u8>,
-    ) -> Result<CompletedPart, String> {
-        self.client
-            .upload_part()
-            .bucket(&self.bucket_name)
-            .key(remote_filename)
-            .upload_id(upload_id)
-            .part_number(part_number)
-            .body(aws_sdk_s3::primitives::ByteStream::from(body))
-            .send()
-            .await
-            .map_err(|e| e.to_string())?
-            .e_tag()
-            .ok_or_else(|| "Failed to get ETag".to_string())
-            .map(|e_tag| {
-                CompletedPart::builder()
-                    .e_tag(e_tag)
-                    .part_number(part_number)
-                    .build()
-            })
+        checksum: Checksum,
+    ) -> Result<CompletedPart, UploadError> {
+        let part_checksum = checksum::digest(checksum, &body);
+        // 重试时复用同一块内存，clone 只增加引用计数
+        let body = Bytes::from(body);
+        let mut attempt = 0;
+        loop {
+            let started = Instant::now();
+            let result = self
+                .client
+                .upload_part()
+                .bucket(&self.bucket_name)
+                .key(remote_filename)
+                .upload_id(upload_id)
+                .part_number(part_number)
+                .set_checksum_sha256(
+                    part_checksum
+                        .clone()
+                        .filter(|_| checksum == Checksum::Sha256),
+                )
+                .set_checksum_crc32_c(
+                    part_checksum
+                        .clone()
+                        .filter(|_| checksum == Checksum::Crc32c),
+                )
+                // 每次发送（包括重试）都要经过限速
+                .body(self.body(body.clone()))
+                .customize()
+                // 重试由下面的循环负责，关闭 SDK 自带的重试以免次数叠加
+                .config_override(
+                    self.transfer_config(body.len())
+                        .retry_config(aws_sdk_s3::config::retry::RetryConfig::disabled()),
+                )
+                .send()
+                .await;
+
+            match result {
+                Ok(output) => {
+                    self.record_transfer(body.len(), started.elapsed());
+                    let e_tag = output
+                        .e_tag()
+                        .ok_or_else(|| "Failed to get ETag".to_string())?;
+                    return Ok(completed_part(
+                        part_number,
+                        e_tag,
+                        checksum,
+                        part_checksum.as_deref(),
+                    ));
+                }
+                Err(e) if attempt < self.retry.max_retries && retry::is_retryable(&e) => {
+                    let delay = retry::backoff_delay(&self.retry, attempt);
+                    attempt += 1;
+                    println!(
+                        "分段 {} 上传失败，{} ms 后第 {} 次重试：{}",
+                        part_number,
+                        delay.as_millis(),
+                        attempt,
+                        e
+                    );
+                    tokio::time::sleep(delay).await;
+                }
+                // 服务端算出的分段校验和与本地不一致
+                Err(e) if e.code() == Some("BadDigest") => {
+                    return Err(UploadError::new(
+                        UploadError::CHECKSUM_MISMATCH,
+                        format!("Part {} checksum mismatch: {}", part_number, e),
+                    ));
+                }
+                Err(e) => return Err(e.to_string().into()),
+            }
+        }
     }
 
     async fn stream_upload_file(
@@ -305,10 +1230,8 @@ impl R2Client {
         path: &str,
         remote_filename: &str,
         file_id: &str,
-    ) -> Result<(), String> {
-        const CHUNK_SIZE: usize = 5 * 1024 * 1024; // 5MB chunks
-        const MAX_CONCURRENT_TASKS: usize = 16; // 最大并发任务数
-
+        headers: &ObjectHeaders,
+    ) -> Result<(), UploadError> {
         // 读取文件信息
         let mut file = tokio::fs::File::open(path)
             .await
@@ -317,7 +1240,7 @@ impl R2Client {
 
         // 首次报告
         emit_progress(
-            &app,
+            app,
             format!("{}/{}", self.domain, remote_filename),
             file_id.to_string(),
             remote_filename.to_string(),
@@ -329,86 +1252,384 @@ impl R2Client {
             },
         );
 
-        // 如果文件小于 CHUNK_SIZE，直接上传
-        if file_size < CHUNK_SIZE {
+        // 如果文件小于最小分段大小，直接上传
+        if file_size < MIN_PART_SIZE as usize {
             let mut buffer = Vec::new();
             file.read_to_end(&mut buffer)
                 .await
                 .map_err(|e| e.to_string())?;
-            self.client
-                .put_object()
+            return self.put_object(remote_filename, buffer, headers).await;
+        }
+
+        // 大文件，分块上传，并把会话写入磁盘以便重启后续传
+        let part_size = part_size_for(file_size as u64, self.part_size)?;
+        // 开启校验和时先顺序读一遍文件，算出整个文件的校验和写入元数据
+        let file_checksum = checksum::file_digest(self.checksum, path).await?;
+        // 读取文件开头用于识别 Content-Type
+        let mut head = Vec::with_capacity(headers::SNIFF_LEN);
+        (&mut file)
+            .take(headers::SNIFF_LEN as u64)
+            .read_to_end(&mut head)
+            .await
+            .map_err(|e| e.to_string())?;
+        let upload_id = self
+            .create_multipart_upload(remote_filename, file_checksum.as_deref(), headers, &head)
+            .await?;
+        let upload_session = self.new_session(
+            file_id,
+            path.to_string(),
+            remote_filename,
+            upload_id,
+            part_size,
+            file_checksum,
+            session::fingerprint(path).await?,
+        );
+        session::save(app, &upload_session).await?;
+
+        self.upload_parts(app, upload_session, PartSource::File(file))
+            .await
+    }
+
+    // 从远程 URL 流式上传，不落地临时文件；Content-Length 未知时读到流结束为止
+    #[allow(clippy::too_many_arguments)]
+    async fn stream_upload_url(
+        &self,
+        app: &tauri::AppHandle,
+        url: &str,
+        request_headers: &HashMap<String, String>,
+        max_bytes: Option<u64>,
+        remote_filename: &str,
+        file_id: &str,
+        headers: &ObjectHeaders,
+    ) -> Result<(), UploadError> {
+        let mut download = fetch::open(url, request_headers, max_bytes).await?;
+        let content_length = download.content_length();
+        emit_progress(
+            app,
+            format!("{}/{}", self.domain, remote_filename),
+            file_id.to_string(),
+            remote_filename.to_string(),
+            UploadStatus::Uploading {
+                progress: 0.0,
+                bytes_uploaded: 0,
+                total_bytes: content_length.unwrap_or_default(),
+                speed: 0.0,
+            },
+        );
+
+        // 远程响应的类型优先于存储桶规则，但不覆盖显式指定的类型；octet-stream 交给内容识别
+        let headers = ObjectHeaders {
+            content_type: download
+                .content_type()
+                .filter(|c| !c.starts_with("application/octet-stream"))
+                .map(str::to_string),
+            ..Default::default()
+        }
+        .merge(Some(headers));
+
+        let part_size = part_size_for(
+            content_length.or(max_bytes).unwrap_or_default(),
+            self.part_size,
+        )?;
+        // 第一段都读不满说明内容不超过一个分段，直接上传
+        let first = download.read(part_size as usize).await?;
+        if (first.len() as u64) < part_size {
+            return self.put_object(remote_filename, first, &headers).await;
+        }
+
+        let upload_id = self
+            .create_multipart_upload(
+                remote_filename,
+                None,
+                &headers,
+                &first[..headers::SNIFF_LEN],
+            )
+            .await?;
+        download.unread(first);
+        let upload_session = self.new_session(
+            file_id,
+            String::new(),
+            remote_filename,
+            upload_id,
+            part_size,
+            None,
+            FileFingerprint {
+                size: content_length.unwrap_or_default(),
+                modified: 0,
+            },
+        );
+
+        self.upload_parts(app, upload_session, PartSource::Stream(download))
+            .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn new_session(
+        &self,
+        file_id: &str,
+        path: String,
+        remote_filename: &str,
+        upload_id: String,
+        part_size: u64,
+        file_checksum: Option<String>,
+        fingerprint: FileFingerprint,
+    ) -> UploadSession {
+        UploadSession {
+            file_id: file_id.to_string(),
+            bucket_name: self.bucket_name.clone(),
+            endpoint: self.endpoint.clone(),
+            domain: self.domain.clone(),
+            path,
+            remote_filename: remote_filename.to_string(),
+            upload_id,
+            part_size,
+            checksum: self.checksum,
+            file_checksum,
+            fingerprint,
+            completed_parts: Vec::new(),
+            created_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs(),
+        }
+    }
+
+    // 续传：确认本地文件未被修改，再用 ListParts 与服务端已有的分段对齐，只上传缺失的分段
+    async fn resume_session(
+        &self,
+        app: &tauri::AppHandle,
+        mut upload_session: UploadSession,
+    ) -> Result<(), UploadError> {
+        if session::fingerprint(&upload_session.path).await? != upload_session.fingerprint {
+            return Err("Local file has changed since the upload started"
+                .to_string()
+                .into());
+        }
+
+        let parts = match self
+            .list_parts(&upload_session.remote_filename, &upload_session.upload_id)
+            .await
+        {
+            Ok(parts) => parts,
+            Err(e) if e.code() == Some("NoSuchUpload") => {
+                // 服务端已经清理了这个分段上传，会话无法继续
+                session::remove(app, &upload_session.file_id).await?;
+                return Err("Multipart upload no longer exists on the server"
+                    .to_string()
+                    .into());
+            }
+            Err(e) => return Err(e.to_string().into()),
+        };
+
+        // 只信任大小与本地分段一致的分段，其余重新上传
+        // 开启校验和时还要求服务端返回了分段校验和，否则无法算出组合校验和
+        let file_size = upload_session.fingerprint.size;
+        let part_size = upload_session.part_size;
+        let checksum = upload_session.checksum;
+        upload_session.completed_parts = parts
+            .iter()
+            .filter_map(|part| {
+                let part_number = part.part_number()?;
+                let offset = (part_number as u64 - 1) * part_size;
+                let expected = part_size.min(file_size.saturating_sub(offset));
+                if part.size() != Some(expected as i64) {
+                    return None;
+                }
+                let part_checksum = match checksum {
+                    Checksum::None => None,
+                    Checksum::Sha256 => Some(part.checksum_sha256()?.to_string()),
+                    Checksum::Crc32c => Some(part.checksum_crc32_c()?.to_string()),
+                };
+                Some(SessionPart {
+                    part_number,
+                    e_tag: part.e_tag()?.to_string(),
+                    checksum: part_checksum,
+                })
+            })
+            .collect();
+        session::save(app, &upload_session).await?;
+
+        let file = tokio::fs::File::open(&upload_session.path)
+            .await
+            .map_err(|e| e.to_string())?;
+        self.upload_parts(app, upload_session, PartSource::File(file))
+            .await
+    }
+
+    async fn list_parts(
+        &self,
+        remote_filename: &str,
+        upload_id: &str,
+    ) -> Result<Vec<Part>, SdkError<ListPartsError>> {
+        let mut parts = Vec::new();
+        let mut part_number_marker = None;
+
+        loop {
+            let output = self
+                .client
+                .list_parts()
                 .bucket(&self.bucket_name)
                 .key(remote_filename)
-                .body(buffer.into())
-                .content_type(
-                    from_path(remote_filename)
-                        .first_or_octet_stream()
-                        .to_string(),
-                )
+                .upload_id(upload_id)
+                .set_part_number_marker(part_number_marker.take())
                 .send()
-                .await
-                .map_err(|e| e.to_string())?;
-            return Ok(());
+                .await?;
+            parts.extend_from_slice(output.parts());
+
+            match output.next_part_number_marker() {
+                Some(marker) if output.is_truncated().unwrap_or(false) => {
+                    part_number_marker = Some(marker.to_string());
+                }
+                _ => break,
+            }
         }
 
-        // 大文件，分块上传
-        let upload_id = self.create_multipart_upload(remote_filename).await?;
+        Ok(parts)
+    }
 
-        // Store upload_id in UPLOAD_TASKS
-        if let Some(mut entry) = UPLOAD_TASKS.get_mut(file_id) {
-            entry.1 = Some(upload_id.clone());
+    // 内存和远程流的内容没有会话文件，失败后无法续传，中止分段上传以免服务端残留已上传的分段
+    async fn upload_parts(
+        &self,
+        app: &tauri::AppHandle,
+        upload_session: UploadSession,
+        source: PartSource,
+    ) -> Result<(), UploadError> {
+        let persistent = source.is_persistent();
+        let remote_filename = upload_session.remote_filename.clone();
+        let upload_id = upload_session.upload_id.clone();
+        let result = self.upload_session_parts(app, upload_session, source).await;
+        if result.is_err() && !persistent {
+            if let Err(e) = self
+                .abort_multipart_upload(&remote_filename, &upload_id)
+                .await
+            {
+                println!("中止分段上传失败：{}", e);
+            }
         }
+        result
+    }
 
-        // Store client and remote_filename for potential abort
-        UPLOAD_TASKS_INFO.insert(
-            file_id.to_string(),
-            (Arc::new(self.clone()), remote_filename.to_string()),
+    async fn upload_session_parts(
+        &self,
+        app: &tauri::AppHandle,
+        upload_session: UploadSession,
+        mut source: PartSource,
+    ) -> Result<(), UploadError> {
+        let persistent = source.is_persistent();
+        let file_id = upload_session.file_id.clone();
+        let remote_filename = upload_session.remote_filename.clone();
+        let upload_id = upload_session.upload_id.clone();
+        let file_size = upload_session.fingerprint.size as usize;
+        let part_size = upload_session.part_size as usize;
+        // 长度未知的远程流一直读到流结束为止，file_size 为 0
+        let size_known = source.is_size_known();
+        let part_count = if size_known {
+            file_size.div_ceil(part_size)
+        } else {
+            MAX_PARTS as usize
+        };
+        let checksum = upload_session.checksum;
+        let completed: HashSet<i32> = upload_session
+            .completed_parts
+            .iter()
+            .map(|part| part.part_number)
+            .collect();
+
+        // Store client, remote_filename and upload_id for potential abort
+        app.state::<UploadTasks>().set_multipart(
+            &file_id,
+            MultipartUpload {
+                client: Arc::new(self.clone()),
+                remote_filename: remote_filename.clone(),
+                upload_id: upload_id.clone(),
+            },
         );
 
+        // 已完成的分段计入进度，但不计入本次的速度
+        let already_uploaded: usize = completed
+            .iter()
+            .map(|&part_number| {
+                let offset = (part_number as usize - 1) * part_size;
+                part_size.min(file_size.saturating_sub(offset))
+            })
+            .sum();
+
         let start_time = SystemTime::now();
-        let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_TASKS)); // 限制并发任务数
-        let mut tasks = Vec::new();
-        let mut part_number = 1;
-        let bytes_uploaded = Arc::new(AtomicUsize::new(0)); // 用于跟踪实际上传的字节数
-        let mut file_offset = 0; // 用于跟踪文件的读取偏移量
+        // JoinSet 被丢弃时会中止其中仍在运行的分段任务
+        let mut tasks = JoinSet::new();
+        let bytes_uploaded = Arc::new(AtomicUsize::new(already_uploaded)); // 用于跟踪实际上传的字节数
+        let upload_session = Arc::new(tokio::sync::Mutex::new(upload_session));
 
-        // 读取文件并分块上传
-        loop {
-            // 获取 Semaphore 许可
-            let semaphore = semaphore.clone();
-            let permit = semaphore.acquire_owned().await.map_err(|e| e.to_string())?;
+        // 读取文件并分块上传，跳过已完成的分段
+        for part_number in 1..=part_count as i32 {
+            if completed.contains(&part_number) {
+                continue;
+            }
+
+            // 暂停时停在这里，不再读取新的分段
+            wait_while_paused(
+                app,
+                format!("{}/{}", self.domain, remote_filename),
+                &file_id,
+                &remote_filename,
+                bytes_uploaded.load(Ordering::SeqCst) as u64,
+                file_size as u64,
+            )
+            .await?;
 
-            let remaining_bytes = file_size - file_offset; // 基于文件偏移量计算剩余字节
-            let buffer_size = if remaining_bytes > CHUNK_SIZE {
-                CHUNK_SIZE
+            let file_offset = (part_number as usize - 1) * part_size;
+            let buffer_size = if size_known {
+                part_size.min(file_size - file_offset)
             } else {
-                remaining_bytes
+                part_size
             };
 
-            if buffer_size == 0 {
-                break; // 文件已读取完毕
+            // 从全局调度器获取分段许可，限制所有文件合计的并发分段数和内存占用
+            let permit = SCHEDULER.acquire_part(buffer_size).await?;
+
+            // 已有分段在重试后仍然失败时不再调度新的分段，直接返回第一个错误
+            while let Some(result) = tasks.try_join_next() {
+                result.map_err(|e| e.to_string())??;
             }
 
-            let mut buffer = vec![0; buffer_size];
-            file.read_exact(&mut buffer)
-                .await
-                .map_err(|e| e.to_string())?;
+            let buffer = source.read(file_offset, buffer_size).await?;
+            if buffer.is_empty() {
+                break;
+            }
+            let buffer_size = buffer.len();
 
             // 克隆需要的变量以在任务中使用
             let client = self.clone();
-            let remote_filename = remote_filename.to_string();
+            let remote_filename = remote_filename.clone();
             let upload_id = upload_id.clone();
             let app = app.clone();
-            let file_id = file_id.to_string();
+            let file_id = file_id.clone();
             let domain = self.domain.clone();
             let bytes_uploaded = bytes_uploaded.clone();
+            let upload_session = upload_session.clone();
 
             // 启动并行上传任务
-            let task = tokio::spawn(async move {
+            tasks.spawn(async move {
                 let part = client
-                    .upload_part(&remote_filename, &upload_id, part_number, buffer.to_vec())
+                    .upload_part(&remote_filename, &upload_id, part_number, buffer, checksum)
                     .await?;
 
+                // 记录已完成的分段，进程退出后可据此续传
+                {
+                    let mut upload_session = upload_session.lock().await;
+                    upload_session.completed_parts.push(SessionPart {
+                        part_number,
+                        e_tag: part.e_tag().unwrap_or_default().to_string(),
+                        checksum: part
+                            .checksum_sha256()
+                            .or(part.checksum_crc32_c())
+                            .map(str::to_string),
+                    });
+                    if persistent {
+                        session::save(&app, &upload_session).await?;
+                    }
+                }
+
                 // 更新实际上传的字节数
                 bytes_uploaded.fetch_add(buffer_size, Ordering::SeqCst);
 
@@ -417,38 +1638,197 @@ impl R2Client {
                     .duration_since(start_time)
                     .unwrap_or_default();
                 let uploaded = bytes_uploaded.load(Ordering::SeqCst);
-                let speed = uploaded as f64 / elapsed.as_secs_f64();
+                let speed = (uploaded - already_uploaded) as f64 / elapsed.as_secs_f64();
+                // 暂停后仍在收尾的分段不应把状态改回 uploading
+                let progress = if size_known {
+                    uploaded as f64 / file_size as f64
+                } else {
+                    0.0
+                };
+                let status = if app.state::<UploadTasks>().is_paused(&file_id) {
+                    UploadStatus::Paused {
+                        progress,
+                        bytes_uploaded: uploaded as u64,
+                        total_bytes: file_size as u64,
+                    }
+                } else {
+                    UploadStatus::Uploading {
+                        progress,
+                        bytes_uploaded: uploaded as u64,
+                        total_bytes: file_size as u64,
+                        speed,
+                    }
+                };
                 emit_progress(
                     &app,
                     format!("{}/{}", domain, remote_filename),
                     file_id,
                     remote_filename,
-                    UploadStatus::Uploading {
-                        progress: uploaded as f64 / file_size as f64,
-                        bytes_uploaded: (part_number as usize * CHUNK_SIZE) as u64,
-                        total_bytes: file_size as u64,
-                        speed,
-                    },
+                    status,
                 );
 
-                // 释放 Semaphore 许可
+                // 释放分段许可
                 drop(permit);
 
-                Ok::<_, String>(part)
+                Ok::<_, UploadError>(())
             });
+        }
+        if !size_known && !source.read(0, 1).await?.is_empty() {
+            return Err(format!(
+                "Remote file is too large for a multipart upload with {} byte parts",
+                part_size
+            )
+            .into());
+        }
 
-            tasks.push(task);
-            file_offset += buffer_size; // 更新文件读取偏移量
-            part_number += 1;
+        // 等待所有任务完成，遇到第一个失败的分段就返回
+        while let Some(result) = tasks.join_next().await {
+            result.map_err(|e| e.to_string())??;
         }
 
-        // 等待所有任务完成
-        let results = futures::future::try_join_all(tasks).await.unwrap();
-        let completed_parts: Vec<_> = results.into_iter().collect::<Result<_, _>>()?;
+        let mut session_parts = upload_session.lock().await.completed_parts.clone();
+        session_parts.sort_by_key(|part| part.part_number);
+        let completed_parts: Vec<CompletedPart> = session_parts
+            .iter()
+            .map(|part| {
+                completed_part(
+                    part.part_number,
+                    &part.e_tag,
+                    checksum,
+                    part.checksum.as_deref(),
+                )
+            })
+            .collect();
 
         // 完成分块上传
-        self.complete_multipart_upload(remote_filename, &upload_id, completed_parts)
+        self.complete_multipart_upload(&remote_filename, &upload_id, completed_parts)
+            .await?;
+        session::remove(app, &file_id).await?;
+
+        // 用各分段的校验和推算对象的组合校验和，与服务端的结果比对
+        let part_checksums: Vec<&str> = session_parts
+            .iter()
+            .filter_map(|part| part.checksum.as_deref())
+            .collect();
+        let expected = checksum::composite(checksum, &part_checksums)?;
+        self.verify_object(
+            &remote_filename,
+            bytes_uploaded.load(Ordering::SeqCst) as u64,
+            checksum,
+            expected.as_deref(),
+        )
+        .await
+    }
+
+    // 上传完成后 HEAD 对象，核对大小和校验和；服务端没有返回校验和时只核对大小
+    async fn verify_object(
+        &self,
+        remote_filename: &str,
+        size: u64,
+        checksum: Checksum,
+        expected: Option<&str>,
+    ) -> Result<(), UploadError> {
+        if checksum == Checksum::None {
+            return Ok(());
+        }
+
+        let output = self
+            .client
+            .head_object()
+            .bucket(&self.bucket_name)
+            .key(remote_filename)
+            .checksum_mode(ChecksumMode::Enabled)
+            .send()
             .await
+            .map_err(|e| e.to_string())?;
+
+        if output.content_length() != Some(size as i64) {
+            return Err(UploadError::new(
+                UploadError::CHECKSUM_MISMATCH,
+                format!(
+                    "Size mismatch after upload: expected {} bytes, got {:?}",
+                    size,
+                    output.content_length()
+                ),
+            ));
+        }
+
+        let actual = match checksum {
+            Checksum::Sha256 => output.checksum_sha256(),
+            Checksum::Crc32c => output.checksum_crc32_c(),
+            Checksum::None => None,
+        };
+        // 组合校验和的 "-分段数" 后缀并非所有服务都会返回，只比较摘要部分
+        let digest = |value: &str| value.split('-').next().unwrap_or_default().to_string();
+        if let (Some(actual), Some(expected)) = (actual, expected) {
+            if digest(actual) != digest(expected) {
+                return Err(UploadError::new(
+                    UploadError::CHECKSUM_MISMATCH,
+                    format!(
+                        "Checksum mismatch after upload: expected {}, got {}",
+                        expected, actual
+                    ),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    // HEAD 远端同名对象，根据冲突策略决定上传到哪个文件名或跳过
+    async fn resolve_conflict(
+        &self,
+        policy: ConflictPolicy,
+        local: Option<Local<'_>>,
+        remote_filename: &str,
+    ) -> Result<Conflict, String> {
+        if policy == ConflictPolicy::Overwrite {
+            return Ok(Conflict::Upload(remote_filename.to_string()));
+        }
+        let Some(existing) = self.head_object(remote_filename).await? else {
+            return Ok(Conflict::Upload(remote_filename.to_string()));
+        };
+
+        match policy {
+            ConflictPolicy::Overwrite => Ok(Conflict::Upload(remote_filename.to_string())),
+            ConflictPolicy::Skip => Ok(Conflict::Skip("EXISTS")),
+            ConflictPolicy::SkipIdentical if is_identical(&existing, local).await? => {
+                Ok(Conflict::Skip("IDENTICAL"))
+            }
+            ConflictPolicy::SkipIdentical => Ok(Conflict::Upload(remote_filename.to_string())),
+            ConflictPolicy::Rename => {
+                for i in 1..=MAX_RENAME_ATTEMPTS {
+                    let candidate = numbered_filename(remote_filename, i);
+                    if self.head_object(&candidate).await?.is_none() {
+                        return Ok(Conflict::Upload(candidate));
+                    }
+                }
+                Err(format!(
+                    "Could not find a free name for {} after {} attempts",
+                    remote_filename, MAX_RENAME_ATTEMPTS
+                ))
+            }
+        }
+    }
+
+    async fn head_object(&self, remote_filename: &str) -> Result<Option<HeadObjectOutput>, String> {
+        match self
+            .client
+            .head_object()
+            .bucket(&self.bucket_name)
+            .key(remote_filename)
+            .send()
+            .await
+        {
+            Ok(output) => Ok(Some(output)),
+            Err(e) if e.as_service_error().is_some_and(|e| e.is_not_found()) => Ok(None),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+
+    fn body(&self, data: Bytes) -> ByteStream {
+        let bucket_key = format!("{}/{}", self.endpoint, self.bucket_name);
+        throttle::body(&bucket_key, self.bandwidth_limit.as_ref(), data)
     }
 
     async fn abort_multipart_upload(
@@ -467,6 +1847,652 @@ impl R2Client {
         Ok(())
     }
 
+    // 列出一页对象；搜索时连续翻页，直到凑满一页匹配结果、列完或达到翻页上限
+    pub async fn list_objects(&self, options: ListOptions) -> Result<ObjectPage, String> {
+        let max_keys = options
+            .max_keys
+            .unwrap_or(MAX_LIST_KEYS)
+            .clamp(1, MAX_LIST_KEYS);
+        let search = options
+            .search
+            .as_deref()
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(str::to_lowercase);
+        let prefix = options.prefix.clone().filter(|p| !p.is_empty());
+        // 搜索时忽略 delimiter，在 prefix 下递归查找
+        let delimiter = match search {
+            Some(_) => None,
+            None => options.delimiter.clone().filter(|d| !d.is_empty()),
+        };
+
+        let mut folders = Vec::new();
+        let mut objects = Vec::new();
+        let mut continuation_token = options.continuation_token.clone();
+        for _ in 0..MAX_SEARCH_PAGES {
+            let output = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket_name)
+                .set_prefix(prefix.clone())
+                .set_delimiter(delimiter.clone())
+                .set_continuation_token(continuation_token.take())
+                .max_keys(if search.is_some() {
+                    MAX_LIST_KEYS
+                } else {
+                    max_keys
+                })
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+
+            folders.extend(
+                output
+                    .common_prefixes()
+                    .iter()
+                    .filter_map(|p| p.prefix().map(str::to_string)),
+            );
+            objects.extend(
+                output
+                    .contents()
+                    .iter()
+                    .filter(|object| match (&search, object.key()) {
+                        (Some(search), Some(key)) => key.to_lowercase().contains(search),
+                        (None, Some(_)) => true,
+                        (_, None) => false,
+                    })
+                    .map(|object| ObjectEntry {
+                        key: object.key().unwrap_or_default().to_string(),
+                        size: object.size().unwrap_or_default() as u64,
+                        last_modified: object
+                            .last_modified()
+                            .and_then(|t| t.to_millis().ok())
+                            .map(|t| t as u64),
+                        e_tag: object.e_tag().map(|e| e.trim_matches('"').to_string()),
+                        content_type: None,
+                    }),
+            );
+
+            continuation_token = output
+                .next_continuation_token()
+                .filter(|_| output.is_truncated().unwrap_or(false))
+                .map(str::to_string);
+            if search.is_none()
+                || continuation_token.is_none()
+                || objects.len() >= max_keys as usize
+            {
+                break;
+            }
+        }
+
+        // ListObjectsV2 不返回 Content-Type，需要时逐个 HEAD，失败的留空
+        if options.include_content_type {
+            objects = futures::stream::iter(objects)
+                .map(|mut object| async move {
+                    object.content_type = self
+                        .head_object(&object.key)
+                        .await
+                        .ok()
+                        .flatten()
+                        .and_then(|output| output.content_type().map(str::to_string));
+                    object
+                })
+                .buffered(HEAD_CONCURRENCY)
+                .collect()
+                .await;
+        }
+
+        match options.sort_by {
+            ObjectSortKey::Key => objects.sort_by(|a, b| a.key.cmp(&b.key)),
+            ObjectSortKey::Size => objects.sort_by_key(|object| object.size),
+            ObjectSortKey::LastModified => objects.sort_by_key(|object| object.last_modified),
+        }
+        if options.descending {
+            objects.reverse();
+            folders.reverse();
+        }
+
+        Ok(ObjectPage {
+            folders,
+            objects,
+            next_continuation_token: continuation_token,
+        })
+    }
+
+    async fn presign(&self, key: &str, options: PresignOptions) -> Result<PresignedUrl, String> {
+        let expires_in = options
+            .expires_in_secs
+            .unwrap_or(DEFAULT_PRESIGN_EXPIRY_SECS);
+        if expires_in == 0 || expires_in > MAX_PRESIGN_EXPIRY_SECS {
+            return Err(format!(
+                "Expiry must be between 1 and {} seconds",
+                MAX_PRESIGN_EXPIRY_SECS
+            ));
+        }
+        let config = PresigningConfig::expires_in(Duration::from_secs(expires_in))
+            .map_err(|e| e.to_string())?;
+
+        let request = match options.method {
+            PresignMethod::Get => self
+                .client
+                .get_object()
+                .bucket(&self.bucket_name)
+                .key(key)
+                .set_response_content_disposition(headers::non_empty(
+                    &options.response_content_disposition,
+                ))
+                .presigned(config)
+                .await
+                .map_err(|e| e.to_string())?,
+            PresignMethod::Put => self
+                .client
+                .put_object()
+                .bucket(&self.bucket_name)
+                .key(key)
+                .set_content_type(headers::non_empty(&options.content_type))
+                .presigned(config)
+                .await
+                .map_err(|e| e.to_string())?,
+        };
+
+        Ok(PresignedUrl {
+            url: request.uri().to_string(),
+            method: request.method().to_string(),
+            headers: request
+                .headers()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+            expires_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+                + expires_in,
+        })
+    }
+
+    // 并发按范围下载到 .part 文件，每完成一段记录进度，中断后再次下载同一对象时只下载缺失的段
+    async fn download_object(
+        &self,
+        app: &AppHandle,
+        task_id: &str,
+        key: &str,
+        path: &Path,
+    ) -> Result<(), String> {
+        let head = self
+            .head_object(key)
+            .await?
+            .ok_or_else(|| format!("Object not found: {}", key))?;
+        let size = head.content_length().unwrap_or_default() as u64;
+        let e_tag = head.e_tag().unwrap_or_default().to_string();
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .map_err(|e| e.to_string())?;
+        }
+        let part_path = download::part_path(path);
+
+        // 远端对象没有变化时续传，否则重新创建 .part 文件
+        let state = match download::load_state(path).await {
+            Some(state) if state.e_tag == e_tag && state.size == size && state.chunk_size > 0 => {
+                state
+            }
+            _ => {
+                let file = tokio::fs::File::create(&part_path)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                file.set_len(size).await.map_err(|e| e.to_string())?;
+                let state = DownloadState {
+                    e_tag: e_tag.clone(),
+                    size,
+                    chunk_size: DOWNLOAD_CHUNK_SIZE,
+                    completed_chunks: Vec::new(),
+                };
+                download::save_state(path, &state).await?;
+                state
+            }
+        };
+
+        let chunk_size = state.chunk_size;
+        let completed: HashSet<u64> = state.completed_chunks.iter().copied().collect();
+        let chunk_len = |index: u64| download::chunk_range(size, chunk_size, index).1;
+        let already_downloaded: u64 = completed.iter().map(|&index| chunk_len(index)).sum();
+
+        let start_time = SystemTime::now();
+        let downloaded = Arc::new(AtomicU64::new(already_downloaded));
+        let state = Arc::new(tokio::sync::Mutex::new(state));
+        let mut tasks = Vec::new();
+
+        for index in 0..download::chunk_count(size, chunk_size) {
+            if completed.contains(&index) {
+                continue;
+            }
+            let (start, len) = download::chunk_range(size, chunk_size, index);
+
+            // 和上传共用分段许可，限制同时进行的范围请求数和内存占用
+            let permit = SCHEDULER.acquire_part(len as usize).await?;
+
+            let client = self.clone();
+            let app = app.clone();
+            let task_id = task_id.to_string();
+            let key = key.to_string();
+            let e_tag = e_tag.clone();
+            let path = path.to_path_buf();
+            let part_path = part_path.clone();
+            let downloaded = downloaded.clone();
+            let state = state.clone();
+
+            let task = tokio::spawn(async move {
+                let data = client
+                    .get_range(&key, &e_tag, start, start + len - 1)
+                    .await?;
+
+                let mut file = tokio::fs::OpenOptions::new()
+                    .write(true)
+                    .open(&part_path)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                file.seek(SeekFrom::Start(start))
+                    .await
+                    .map_err(|e| e.to_string())?;
+                file.write_all(&data).await.map_err(|e| e.to_string())?;
+                file.flush().await.map_err(|e| e.to_string())?;
+                drop(permit);
+
+                {
+                    let mut state = state.lock().await;
+                    state.completed_chunks.push(index);
+                    download::save_state(&path, &state).await?;
+                }
+
+                let bytes_downloaded = downloaded.fetch_add(len, Ordering::SeqCst) + len;
+                let elapsed = SystemTime::now()
+                    .duration_since(start_time)
+                    .unwrap_or_default();
+                let speed = (bytes_downloaded - already_downloaded) as f64 / elapsed.as_secs_f64();
+                let eta = (speed > 0.0).then(|| (size - bytes_downloaded) as f64 / speed);
+                emit_download_progress(
+                    &app,
+                    &task_id,
+                    &key,
+                    &path,
+                    DownloadStatus::Downloading {
+                        progress: bytes_downloaded as f64 / size as f64,
+                        bytes_downloaded,
+                        total_bytes: size,
+                        speed,
+                        eta,
+                    },
+                );
+
+                Ok::<_, String>(())
+            });
+            tasks.push(task);
+        }
+
+        let results = futures::future::try_join_all(tasks)
+            .await
+            .map_err(|e| e.to_string())?;
+        results.into_iter().collect::<Result<Vec<_>, _>>()?;
+
+        tokio::fs::rename(&part_path, path)
+            .await
+            .map_err(|e| e.to_string())?;
+        download::remove_state(path).await?;
+
+        // 上传时记录了修改时间的对象，下载后恢复本地文件的修改时间
+        if let Some(mtime) = head
+            .metadata()
+            .and_then(|m| m.get(download::MTIME_METADATA_KEY))
+            .and_then(|v| download::parse_mtime(v))
+        {
+            download::set_mtime(path, mtime).await?;
+        }
+        Ok(())
+    }
+
+    // 下载 start..=end 范围，带 If-Match 防止下载途中对象被替换；响应体读取中断也会重试
+    async fn get_range(
+        &self,
+        key: &str,
+        e_tag: &str,
+        start: u64,
+        end: u64,
+    ) -> Result<Vec<u8>, String> {
+        let mut attempt = 0;
+        loop {
+            let result = self
+                .client
+                .get_object()
+                .bucket(&self.bucket_name)
+                .key(key)
+                .set_if_match(Some(e_tag.to_string()).filter(|e| !e.is_empty()))
+                .range(format!("bytes={}-{}", start, end))
+                .customize()
+                .config_override(
+                    aws_sdk_s3::config::Builder::default()
+                        .retry_config(aws_sdk_s3::config::retry::RetryConfig::disabled()),
+                )
+                .send()
+                .await;
+
+            let error = match result {
+                Ok(output) => match output.body.collect().await {
+                    Ok(data) => {
+                        let data = data.into_bytes();
+                        if data.len() as u64 == end - start + 1 {
+                            return Ok(data.to_vec());
+                        }
+                        format!(
+                            "Expected {} bytes but received {}",
+                            end - start + 1,
+                            data.len()
+                        )
+                    }
+                    Err(e) => e.to_string(),
+                },
+                Err(e) if retry::is_retryable(&e) => e.to_string(),
+                Err(e) => return Err(e.to_string()),
+            };
+
+            if attempt >= self.retry.max_retries {
+                return Err(error);
+            }
+            let delay = retry::backoff_delay(&self.retry, attempt);
+            attempt += 1;
+            println!(
+                "{} 的 {}-{} 下载失败，{} ms 后第 {} 次重试：{}",
+                key,
+                start,
+                end,
+                delay.as_millis(),
+                attempt,
+                error
+            );
+            tokio::time::sleep(delay).await;
+        }
+    }
+
+    async fn list_keys(&self, prefix: &str) -> Result<Vec<String>, String> {
+        let mut keys = Vec::new();
+        let mut continuation_token = None;
+
+        loop {
+            let output = self
+                .client
+                .list_objects_v2()
+                .bucket(&self.bucket_name)
+                .prefix(prefix)
+                .set_continuation_token(continuation_token.take())
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+            keys.extend(
+                output
+                    .contents()
+                    .iter()
+                    .filter_map(|object| object.key().map(str::to_string)),
+            );
+
+            match output.next_continuation_token() {
+                Some(token) if output.is_truncated().unwrap_or(false) => {
+                    continuation_token = Some(token.to_string());
+                }
+                _ => break,
+            }
+        }
+
+        Ok(keys)
+    }
+
+    async fn copy_items(
+        &self,
+        app: &AppHandle,
+        task_id: &str,
+        items: Vec<CopyItem>,
+        target_bucket: Option<String>,
+        delete_source: bool,
+    ) -> Result<CopyResult, String> {
+        let target = R2Client {
+            bucket_name: target_bucket
+                .filter(|b| !b.is_empty())
+                .unwrap_or_else(|| self.bucket_name.clone()),
+            ..self.clone()
+        };
+        if target.bucket_name == self.bucket_name {
+            if let Some(key) = target_overwrites_source(&items) {
+                return Err(format!("Target key {} is also a source key", key));
+            }
+        }
+        let total = items.len() as u64;
+        let mut result = CopyResult::default();
+        let mut copied = Vec::new();
+
+        let mut copies = futures::stream::iter(items)
+            .map(|item| {
+                let target = &target;
+                async move {
+                    let copy = self
+                        .copy_object(&item.source_key, target, &item.target_key)
+                        .await;
+                    (item, copy)
+                }
+            })
+            .buffer_unordered(COPY_CONCURRENCY);
+        while let Some((item, copy)) = copies.next().await {
+            match copy {
+                Ok(()) => {
+                    result.copied += 1;
+                    copied.push(item.source_key);
+                }
+                Err(message) => result.failed.push(CopyFailure {
+                    source_key: item.source_key,
+                    target_key: item.target_key,
+                    message,
+                }),
+            }
+            emit_copy_progress(app, task_id, &result, total, false);
+        }
+
+        // 移动：全部复制并校验成功后才删除源对象
+        if delete_source && result.failed.is_empty() {
+            let mut deleted = DeleteResult::default();
+            for batch in copied.chunks(MAX_DELETE_KEYS) {
+                self.delete_batch(batch, &mut deleted).await;
+            }
+            result.deleted = deleted.deleted;
+            result.delete_failed = deleted.failed;
+        }
+
+        emit_copy_progress(app, task_id, &result, total, true);
+        Ok(result)
+    }
+
+    // 复制单个对象到 target 存储桶，完成后 HEAD 目标对象核对大小和 ETag
+    async fn copy_object(
+        &self,
+        source_key: &str,
+        target: &R2Client,
+        target_key: &str,
+    ) -> Result<(), String> {
+        if target.bucket_name == self.bucket_name && source_key == target_key {
+            return Err("Source and target are the same object".to_string());
+        }
+        let source = self
+            .head_object(source_key)
+            .await?
+            .ok_or_else(|| format!("Source object not found: {}", source_key))?;
+        let size = source.content_length().unwrap_or_default() as u64;
+        let copy_source = copy_source(&self.bucket_name, source_key);
+
+        if size <= MAX_COPY_SIZE {
+            target
+                .client
+                .copy_object()
+                .bucket(&target.bucket_name)
+                .key(target_key)
+                .copy_source(&copy_source)
+                .send()
+                .await
+                .map_err(|e| e.to_string())?;
+        } else {
+            target
+                .multipart_copy(&source, &copy_source, size, target_key)
+                .await?;
+        }
+
+        let copied = target
+            .head_object(target_key)
+            .await?
+            .ok_or_else(|| format!("Copied object not found: {}", target_key))?;
+        if copied.content_length() != source.content_length() {
+            return Err(format!(
+                "Size mismatch after copying {}: expected {} bytes, got {:?}",
+                source_key,
+                size,
+                copied.content_length()
+            ));
+        }
+        // 单次 PUT 的对象 ETag 即内容的 MD5，CopyObject 后应保持不变；分段对象的 ETag 无法比较
+        if size <= MAX_COPY_SIZE
+            && source.e_tag().is_some_and(|e| !e.contains('-'))
+            && copied.e_tag() != source.e_tag()
+        {
+            return Err(format!("ETag mismatch after copying {}", source_key));
+        }
+        Ok(())
+    }
+
+    // 超过 5GiB 的对象按范围分段复制，请求头和元数据从源对象带过来；失败时中止分段上传
+    async fn multipart_copy(
+        &self,
+        source: &HeadObjectOutput,
+        copy_source: &str,
+        size: u64,
+        target_key: &str,
+    ) -> Result<(), String> {
+        let headers = ObjectHeaders {
+            content_type: source.content_type().map(str::to_string),
+            cache_control: source.cache_control().map(str::to_string),
+            content_disposition: source.content_disposition().map(str::to_string),
+            content_encoding: source.content_encoding().map(str::to_string),
+            content_language: source.content_language().map(str::to_string),
+            expires: source.expires_string().map(str::to_string),
+            metadata: source.metadata().cloned().unwrap_or_default(),
+        };
+        let upload_id = apply_headers!(
+            self.client
+                .create_multipart_upload()
+                .bucket(&self.bucket_name)
+                .key(target_key),
+            &headers,
+            target_key,
+            &[]
+        )
+        .send()
+        .await
+        .map_err(|e| e.to_string())?
+        .upload_id()
+        .ok_or_else(|| "Failed to get upload ID".to_string())?
+        .to_string();
+
+        let part_size = part_size_for(size, Some(COPY_PART_SIZE))?;
+        let parts = futures::stream::iter(1..=size.div_ceil(part_size))
+            .map(|part_number| {
+                let upload_id = &upload_id;
+                let start = (part_number - 1) * part_size;
+                let end = (start + part_size).min(size) - 1;
+                async move {
+                    let output = self
+                        .client
+                        .upload_part_copy()
+                        .bucket(&self.bucket_name)
+                        .key(target_key)
+                        .upload_id(upload_id)
+                        .part_number(part_number as i32)
+                        .copy_source(copy_source)
+                        .copy_source_range(format!("bytes={}-{}", start, end))
+                        .send()
+                        .await
+                        .map_err(|e| e.to_string())?;
+                    let e_tag = output
+                        .copy_part_result()
+                        .and_then(|result| result.e_tag())
+                        .ok_or_else(|| "Missing ETag in UploadPartCopy response".to_string())?;
+                    Ok::<_, String>(
+                        CompletedPart::builder()
+                            .part_number(part_number as i32)
+                            .e_tag(e_tag)
+                            .build(),
+                    )
+                }
+            })
+            .buffered(COPY_CONCURRENCY)
+            .try_collect::<Vec<_>>()
+            .await;
+
+        let completed = match parts {
+            Ok(parts) => {
+                self.complete_multipart_upload(target_key, &upload_id, parts)
+                    .await
+            }
+            Err(e) => Err(e),
+        };
+        if completed.is_err() {
+            let _ = self.abort_multipart_upload(target_key, &upload_id).await;
+        }
+        completed
+    }
+
+    // quiet 模式下服务端只返回失败的 key，其余都算删除成功
+    async fn delete_batch(&self, keys: &[String], result: &mut DeleteResult) {
+        let objects = keys
+            .iter()
+            .map(|key| ObjectIdentifier::builder().key(key).build())
+            .collect::<Result<Vec<_>, _>>();
+        let output = match objects {
+            Ok(objects) => match Delete::builder()
+                .set_objects(Some(objects))
+                .quiet(true)
+                .build()
+            {
+                Ok(delete) => self
+                    .client
+                    .delete_objects()
+                    .bucket(&self.bucket_name)
+                    .delete(delete)
+                    .send()
+                    .await
+                    .map_err(|e| (e.code().unwrap_or("DeleteError").to_string(), e.to_string())),
+                Err(e) => Err(("DeleteError".to_string(), e.to_string())),
+            },
+            Err(e) => Err(("DeleteError".to_string(), e.to_string())),
+        };
+
+        match output {
+            Ok(output) => {
+                let failed: Vec<DeleteFailure> = output
+                    .errors()
+                    .iter()
+                    .map(|error| DeleteFailure {
+                        key: error.key().unwrap_or_default().to_string(),
+                        code: error.code().unwrap_or_default().to_string(),
+                        message: error.message().unwrap_or_default().to_string(),
+                    })
+                    .collect();
+                result.deleted += (keys.len() - failed.len()) as u64;
+                result.failed.extend(failed);
+            }
+            Err((code, message)) => {
+                result.failed.extend(keys.iter().map(|key| DeleteFailure {
+                    key: key.clone(),
+                    code: code.clone(),
+                    message: message.clone(),
+                }));
+            }
+        }
+    }
+
     pub async fn ping(&self) -> Result<(), String> {
         println!("ping...");
         self.client
@@ -479,20 +2505,294 @@ impl R2Client {
     }
 }
 
-fn create_proxy_connector() -> Option<ProxyConnector<HttpConnector>> {
-    #[cfg(any(target_os = "ios", target_os = "android"))]
-    return None;
+// 大小一致且校验和元数据或 ETag（单次 PUT 时即 MD5）与本地内容一致时认为内容相同
+// 无法读取本地内容时（local 为 None）视为不相同
+async fn is_identical(
+    existing: &HeadObjectOutput,
+    local: Option<Local<'_>>,
+) -> Result<bool, String> {
+    let Some(local) = local else {
+        return Ok(false);
+    };
+    let local_size = match local {
+        Local::Path(path) => tokio::fs::metadata(path)
+            .await
+            .map_err(|e| e.to_string())?
+            .len(),
+        Local::Bytes(bytes) => bytes.len() as u64,
+    };
+    if existing.content_length() != Some(local_size as i64) {
+        return Ok(false);
+    }
+
+    let metadata = existing.metadata();
+    for algorithm in [Checksum::Sha256, Checksum::Crc32c] {
+        let Some(remote) = metadata.and_then(|m| m.get(checksum::metadata_key(algorithm))) else {
+            continue;
+        };
+        let digest = match local {
+            Local::Path(path) => checksum::file_digest(algorithm, path).await?,
+            Local::Bytes(bytes) => checksum::digest(algorithm, bytes),
+        };
+        return Ok(digest.as_ref() == Some(remote));
+    }
 
-    #[cfg(not(any(target_os = "ios", target_os = "android")))]
-    match sysproxy::Sysproxy::get_system_proxy() {
-        Ok(proxy) if !proxy.host.is_empty() && proxy.port > 0 && proxy.enable => {
-            // Try to create proxy URI and connector
-            let proxy_uri = format!("http://{}:{}", proxy.host, proxy.port)
-                .parse()
-                .ok()?;
-            let proxy = hyper_proxy::Proxy::new(hyper_proxy::Intercept::All, proxy_uri);
-            ProxyConnector::from_proxy(HttpConnector::new(), proxy).ok()
+    // 分段上传的 ETag 带有 "-分段数"，不是内容的 MD5，无法比较
+    let Some(e_tag) = existing.e_tag().map(|e| e.trim_matches('"')) else {
+        return Ok(false);
+    };
+    if e_tag.contains('-') {
+        return Ok(false);
+    }
+    let digest = match local {
+        Local::Path(path) => checksum::file_md5(path).await?,
+        Local::Bytes(bytes) => checksum::md5(bytes),
+    };
+    Ok(digest == e_tag)
+}
+
+// CopySource 为 bucket/key，key 需要 URL 编码，保留 /
+fn copy_source(bucket_name: &str, key: &str) -> String {
+    let mut encoded = format!("{}/", bucket_name);
+    for byte in key.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
         }
-        _ => None, // Return None if no proxy or error getting proxy
+    }
+    encoded
+}
+
+// photos/name.png -> photos/name (1).png
+fn numbered_filename(remote_filename: &str, n: u32) -> String {
+    let (dir, name) = match remote_filename.rsplit_once('/') {
+        Some((dir, name)) => (format!("{}/", dir), name),
+        None => (String::new(), remote_filename),
+    };
+    match name.rsplit_once('.') {
+        Some((stem, ext)) if !stem.is_empty() => format!("{}{} ({}).{}", dir, stem, n, ext),
+        _ => format!("{}{} ({})", dir, name, n),
+    }
+}
+
+fn completed_part(
+    part_number: i32,
+    e_tag: &str,
+    checksum: Checksum,
+    part_checksum: Option<&str>,
+) -> CompletedPart {
+    let builder = CompletedPart::builder()
+        .e_tag(e_tag)
+        .part_number(part_number);
+    match (checksum, part_checksum) {
+        (Checksum::Sha256, Some(part_checksum)) => builder.checksum_sha256(part_checksum),
+        (Checksum::Crc32c, Some(part_checksum)) => builder.checksum_crc32_c(part_checksum),
+        _ => builder,
+    }
+    .build()
+}
+
+// 根据文件大小选择分段大小：优先使用存储桶配置，但保证分段数不超过 MAX_PARTS，
+// 结果限制在 MIN_PART_SIZE..=MAX_PART_SIZE 之间并向上取整到 MiB
+fn part_size_for(file_size: u64, preferred: Option<u64>) -> Result<u64, String> {
+    let part_size = preferred
+        .unwrap_or(MIN_PART_SIZE)
+        .max(file_size.div_ceil(MAX_PARTS))
+        .clamp(MIN_PART_SIZE, MAX_PART_SIZE)
+        .div_ceil(MIB)
+        * MIB;
+
+    if file_size.div_ceil(part_size) > MAX_PARTS {
+        return Err(format!(
+            "File is too large for a multipart upload: {} bytes",
+            file_size
+        ));
+    }
+    Ok(part_size)
+}
+
+fn endpoint_url(bucket: &Bucket) -> Result<Option<String>, String> {
+    let endpoint = bucket
+        .endpoint
+        .as_deref()
+        .map(|e| e.trim().trim_end_matches('/'))
+        .filter(|e| !e.is_empty());
+
+    match (bucket.bucket_type, endpoint) {
+        (_, Some(endpoint))
+            if endpoint.starts_with("http://") || endpoint.starts_with("https://") =>
+        {
+            Ok(Some(endpoint.to_string()))
+        }
+        (_, Some(endpoint)) => Err(format!("Invalid endpoint URL: {}", endpoint)),
+        (BucketType::R2, None) if bucket.account_id.is_empty() => {
+            Err("Account ID is required for R2 buckets".to_string())
+        }
+        (BucketType::R2, None) => {
+            let host = match bucket.jurisdiction {
+                Jurisdiction::Default => "r2.cloudflarestorage.com",
+                Jurisdiction::Eu => "eu.r2.cloudflarestorage.com",
+                Jurisdiction::Fedramp => "fedramp.r2.cloudflarestorage.com",
+            };
+            Ok(Some(format!("https://{}.{}", bucket.account_id, host)))
+        }
+        (BucketType::S3, None) => Ok(None),
+    }
+}
+
+fn timeout_config(timeouts: &Timeouts) -> TimeoutConfig {
+    let secs = |secs: Option<u64>| secs.map(Duration::from_secs);
+    let mut builder = TimeoutConfig::builder();
+    builder
+        .set_connect_timeout(secs(timeouts.connect_secs))
+        .set_read_timeout(secs(timeouts.read_secs))
+        .set_operation_timeout(secs(timeouts.operation_secs))
+        .set_operation_attempt_timeout(secs(timeouts.operation_attempt_secs));
+    builder.build()
+}
+
+// 按观测到的速率估算耗时再乘以系数，不低于存储桶的 read 超时；read 不限制时单次请求也不限制
+// rate 为 0 表示还没有观测到速率；限速时所有在途分段共享速率，按最坏情况估算
+fn transfer_timeout(
+    len: usize,
+    rate: u64,
+    limit: Option<u64>,
+    read_secs: Option<u64>,
+) -> Option<Duration> {
+    let read = Duration::from_secs(read_secs?);
+    let rate = match rate {
+        0 => INITIAL_TRANSFER_RATE,
+        rate => rate,
+    };
+    let rate = match limit {
+        Some(limit) => rate.min((limit / MAX_IN_FLIGHT_PARTS as u64).max(1)),
+        None => rate,
+    };
+    let expected = Duration::from_secs_f64(len as f64 / rate as f64);
+    Some((expected * TRANSFER_TIMEOUT_FACTOR).max(read))
+}
+
+fn region(bucket: &Bucket) -> String {
+    match bucket.region.as_deref().map(str::trim) {
+        Some(region) if !region.is_empty() => region.to_string(),
+        _ => match bucket.bucket_type {
+            BucketType::R2 => "auto".to_string(),
+            BucketType::S3 => "us-east-1".to_string(),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GIB: u64 = 1024 * MIB;
+
+    #[test]
+    fn part_size_for_small_files_uses_minimum() {
+        assert_eq!(part_size_for(0, None), Ok(MIN_PART_SIZE));
+        assert_eq!(part_size_for(100 * MIB, None), Ok(MIN_PART_SIZE));
+        assert_eq!(part_size_for(100 * MIB, Some(MIB)), Ok(MIN_PART_SIZE));
+    }
+
+    #[test]
+    fn part_size_for_rounds_preferred_size_up_to_mib() {
+        assert_eq!(part_size_for(GIB, Some(16 * MIB)), Ok(16 * MIB));
+        assert_eq!(part_size_for(GIB, Some(15 * MIB / 2)), Ok(8 * MIB));
+        assert_eq!(part_size_for(GIB, Some(10 * GIB)), Ok(MAX_PART_SIZE));
+    }
+
+    #[test]
+    fn part_size_for_stays_within_part_limit() {
+        for file_size in [100 * GIB, 1024 * GIB + 1, 5 * 1024 * GIB] {
+            let part_size = part_size_for(file_size, Some(MIN_PART_SIZE)).unwrap();
+            assert_eq!(part_size % MIB, 0);
+            assert!(file_size.div_ceil(part_size) <= MAX_PARTS);
+        }
+        assert_eq!(part_size_for(100 * GIB, None), Ok(11 * MIB));
+    }
+
+    #[test]
+    fn part_size_for_rejects_files_over_the_limit() {
+        let max = MAX_PARTS * MAX_PART_SIZE;
+        assert_eq!(part_size_for(max, None), Ok(MAX_PART_SIZE));
+        assert!(part_size_for(max + 1, None).is_err());
+    }
+
+    #[test]
+    fn numbered_filename_inserts_number_before_extension() {
+        assert_eq!(numbered_filename("a.png", 1), "a (1).png");
+        assert_eq!(numbered_filename("img/a.tar.gz", 2), "img/a.tar (2).gz");
+        assert_eq!(numbered_filename("README", 3), "README (3)");
+    }
+
+    #[test]
+    fn numbered_filename_ignores_dots_outside_the_file_stem() {
+        assert_eq!(numbered_filename(".env", 1), ".env (1)");
+        assert_eq!(numbered_filename("v1.2/notes", 1), "v1.2/notes (1)");
+        assert_eq!(numbered_filename("a/b/.gitignore", 4), "a/b/.gitignore (4)");
+    }
+
+    fn copy_item(source_key: &str, target_key: &str) -> CopyItem {
+        CopyItem {
+            source_key: source_key.to_string(),
+            target_key: target_key.to_string(),
+        }
+    }
+
+    #[test]
+    fn copy_prefixes_map_to_folders() {
+        assert_eq!(folder_prefix_or_root(""), "");
+        assert_eq!(folder_prefix_or_root("a"), "a/");
+        assert_eq!(folder_prefix_or_root("a/"), "a/");
+    }
+
+    #[test]
+    fn nested_prefixes_overlap() {
+        assert!(prefixes_overlap("a/", "a/b/"));
+        assert!(prefixes_overlap("a/b/", "a/"));
+        assert!(prefixes_overlap("a/", "a/"));
+        assert!(prefixes_overlap("", "a/"));
+        assert!(!prefixes_overlap("a/", "ab/"));
+        assert!(!prefixes_overlap("a/", "b/a/"));
+    }
+
+    #[test]
+    fn target_overwriting_another_source_is_detected() {
+        let items = vec![copy_item("a/x", "a/b/x"), copy_item("a/b/x", "a/b/b/x")];
+        assert_eq!(target_overwrites_source(&items), Some("a/b/x"));
+
+        let items = vec![copy_item("a/x", "b/x"), copy_item("a/y", "b/y")];
+        assert_eq!(target_overwrites_source(&items), None);
+        assert_eq!(target_overwrites_source(&[copy_item("a", "a")]), None);
+    }
+
+    #[test]
+    fn transfer_timeout_scales_with_rate_and_keeps_read_floor() {
+        // 1KiB 在 10MiB/s 下只需要不到 1ms，仍然取 read 超时
+        let timeout = transfer_timeout(1024, 10 * MIB, None, Some(30));
+        assert_eq!(timeout, Some(Duration::from_secs(30)));
+
+        // 还没有速率时按 64KiB/s 估算：64MiB 需要 1024s，乘以系数 4
+        let timeout = transfer_timeout(64 * MIB as usize, 0, None, Some(30));
+        assert_eq!(timeout, Some(Duration::from_secs(4096)));
+
+        // 限速 4MiB/s 时每个在途分段按 4MiB/s / MAX_IN_FLIGHT_PARTS 估算
+        let per_part = 4 * MIB / MAX_IN_FLIGHT_PARTS as u64;
+        let timeout = transfer_timeout(MIB as usize, 10 * MIB, Some(4 * MIB), Some(1)).unwrap();
+        let expected =
+            Duration::from_secs_f64(MIB as f64 / per_part as f64) * TRANSFER_TIMEOUT_FACTOR;
+        assert_eq!(timeout, expected.max(Duration::from_secs(1)));
+    }
+
+    #[test]
+    fn transfer_timeout_unlimited_when_read_is_unlimited() {
+        assert_eq!(transfer_timeout(1024, 10 * MIB, None, None), None);
+        assert_eq!(
+            transfer_timeout(64 * MIB as usize, 0, Some(MIB), None),
+            None
+        );
     }
 }
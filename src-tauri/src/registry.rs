@@ -0,0 +1,55 @@
+use crate::connector;
+use crate::r2::R2Client;
+use crate::typ::Bucket;
+use dashmap::DashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use zeroize::Zeroizing;
+
+// 放在 Tauri 托管状态中的客户端缓存，同一个存储桶的各个批次复用 SDK 配置和连接池
+#[derive(Default)]
+pub struct ClientRegistry {
+    // 键是 bucket_id，值是创建客户端时的配置哈希和客户端
+    clients: DashMap<u64, (u64, Arc<R2Client>)>,
+}
+
+impl ClientRegistry {
+    // 存储桶配置（包括密钥）或代理设置变化后哈希不同，自动重建客户端
+    // 系统代理模式下只在代理设置变化时重新读取系统代理
+    // bucket_id 为空时是尚未保存的配置，直接创建客户端，不放进缓存
+    pub async fn get(
+        &self,
+        bucket_id: Option<u64>,
+        bucket: &Bucket,
+    ) -> Result<Arc<R2Client>, String> {
+        let proxy = connector::proxy();
+        let Some(bucket_id) = bucket_id else {
+            return Ok(Arc::new(R2Client::new(bucket, &proxy).await?));
+        };
+        let hash = {
+            // 序列化结果包含密钥，算完哈希后清零
+            let config = Zeroizing::new(
+                serde_json::to_string(&(bucket, &proxy)).map_err(|e| e.to_string())?,
+            );
+            let mut hasher = DefaultHasher::new();
+            config.hash(&mut hasher);
+            hasher.finish()
+        };
+
+        if let Some(entry) = self.clients.get(&bucket_id) {
+            if entry.0 == hash {
+                return Ok(entry.1.clone());
+            }
+        }
+
+        let client = Arc::new(R2Client::new(bucket, &proxy).await?);
+        self.clients.insert(bucket_id, (hash, client.clone()));
+        Ok(client)
+    }
+
+    // 删除存储桶后丢弃缓存的客户端
+    pub fn remove(&self, bucket_id: u64) {
+        self.clients.remove(&bucket_id);
+    }
+}
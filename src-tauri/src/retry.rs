@@ -0,0 +1,54 @@
+use crate::typ::RetryConfig;
+use aws_sdk_s3::config::http::HttpResponse;
+use aws_sdk_s3::error::{ProvideErrorMetadata, SdkError};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+// 服务端返回这些错误码时说明是临时问题，可以重试
+const RETRYABLE_CODES: &[&str] = &[
+    "SlowDown",
+    "RequestTimeout",
+    "InternalError",
+    "ServiceUnavailable",
+    "Throttling",
+    "ThrottlingException",
+];
+
+// 超时、连接失败（包括连接被重置）、5xx 和 429/SlowDown 可重试；鉴权等其他 4xx 错误直接失败
+pub fn is_retryable<E: ProvideErrorMetadata>(err: &SdkError<E, HttpResponse>) -> bool {
+    match err {
+        SdkError::TimeoutError(_) | SdkError::ResponseError(_) => true,
+        SdkError::DispatchFailure(failure) => !failure.is_user(),
+        SdkError::ServiceError(service_error) => {
+            let status = service_error.raw().status();
+            status.is_server_error()
+                || status.as_u16() == 429
+                || service_error
+                    .err()
+                    .code()
+                    .is_some_and(|code| RETRYABLE_CODES.contains(&code))
+        }
+        _ => false,
+    }
+}
+
+// 第 attempt 次重试前的等待时间：指数退避，上限 max_delay_ms，并在后一半区间内随机抖动
+pub fn backoff_delay(config: &RetryConfig, attempt: u32) -> Duration {
+    let exp = config
+        .base_delay_ms
+        .saturating_mul(1u64 << attempt.min(20))
+        .min(config.max_delay_ms);
+    let half = exp / 2;
+    let jitter = if half > 0 {
+        random_u64() % (half + 1)
+    } else {
+        0
+    };
+    Duration::from_millis(half + jitter)
+}
+
+// RandomState 每次创建都会使用新的随机种子，足够用于抖动，无需引入 rand
+fn random_u64() -> u64 {
+    RandomState::new().build_hasher().finish()
+}
@@ -0,0 +1,63 @@
+use once_cell::sync::Lazy;
+use std::sync::Arc;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+const MAX_CONCURRENT_FILES: usize = 4; // 同时上传的文件数
+pub const MAX_IN_FLIGHT_PARTS: usize = 16; // 所有文件合计同时上传的分段数
+const MAX_BUFFERED_KIB: u32 = 256 * 1024; // 所有分段合计占用的内存（KiB），256MiB
+
+// 所有批次共享的上传调度器，超出限制的文件和分段在这里排队
+pub static SCHEDULER: Lazy<Scheduler> = Lazy::new(Scheduler::new);
+
+pub struct Scheduler {
+    files: Arc<Semaphore>,
+    parts: Arc<Semaphore>,
+    buffered: Arc<Semaphore>,
+}
+
+// 分段许可，持有期间占用一个分段名额和对应大小的内存额度，drop 时一起归还
+pub struct PartPermit {
+    _part: OwnedSemaphorePermit,
+    _buffered: OwnedSemaphorePermit,
+}
+
+impl Scheduler {
+    fn new() -> Self {
+        Self {
+            files: Arc::new(Semaphore::new(MAX_CONCURRENT_FILES)),
+            parts: Arc::new(Semaphore::new(MAX_IN_FLIGHT_PARTS)),
+            buffered: Arc::new(Semaphore::new(MAX_BUFFERED_KIB as usize)),
+        }
+    }
+
+    pub async fn acquire_file(&self) -> Result<OwnedSemaphorePermit, String> {
+        self.files
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| e.to_string())
+    }
+
+    // 在读取分段数据之前调用，先拿分段名额再拿内存额度，顺序固定避免互相等待
+    pub async fn acquire_part(&self, size: usize) -> Result<PartPermit, String> {
+        let part = self
+            .parts
+            .clone()
+            .acquire_owned()
+            .await
+            .map_err(|e| e.to_string())?;
+        // 超过总额度的大分段按总额度计算，否则永远拿不到许可
+        let kib = (size.div_ceil(1024) as u64).clamp(1, MAX_BUFFERED_KIB as u64) as u32;
+        let buffered = self
+            .buffered
+            .clone()
+            .acquire_many_owned(kib)
+            .await
+            .map_err(|e| e.to_string())?;
+
+        Ok(PartPermit {
+            _part: part,
+            _buffered: buffered,
+        })
+    }
+}
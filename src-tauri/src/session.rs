@@ -0,0 +1,100 @@
+use crate::atomic_file;
+use crate::typ::{FileFingerprint, UploadSession};
+use std::path::{Component, Path, PathBuf};
+use std::time::UNIX_EPOCH;
+use tauri::{AppHandle, Manager};
+
+// 分段上传会话持久化在 {app_data_dir}/sessions/{file_id}.json，应用重启后据此续传
+fn sessions_dir(app: &AppHandle) -> Result<PathBuf, String> {
+    app.path()
+        .app_data_dir()
+        .map(|dir| dir.join("sessions"))
+        .map_err(|e| e.to_string())
+}
+
+// file_id 来自前端，只接受单个普通路径段，防止 ../ 之类的路径穿越
+fn session_path(app: &AppHandle, file_id: &str) -> Result<PathBuf, String> {
+    let mut components = Path::new(file_id).components();
+    if !matches!(
+        (components.next(), components.next()),
+        (Some(Component::Normal(_)), None)
+    ) {
+        return Err(format!("Invalid upload file id: {}", file_id));
+    }
+    Ok(sessions_dir(app)?.join(format!("{}.json", file_id)))
+}
+
+pub async fn save(app: &AppHandle, session: &UploadSession) -> Result<(), String> {
+    let dir = sessions_dir(app)?;
+    tokio::fs::create_dir_all(&dir)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    let data = serde_json::to_vec_pretty(session).map_err(|e| e.to_string())?;
+    let path = session_path(app, &session.file_id)?;
+    atomic_file::write_async(&path, data).await
+}
+
+pub async fn load(app: &AppHandle, file_id: &str) -> Result<Option<UploadSession>, String> {
+    let path = session_path(app, file_id)?;
+    match tokio::fs::read(&path).await {
+        Ok(data) => serde_json::from_slice(&data)
+            .map(Some)
+            .map_err(|e| e.to_string()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+pub async fn load_all(app: &AppHandle) -> Result<Vec<UploadSession>, String> {
+    let dir = sessions_dir(app)?;
+    let mut entries = match tokio::fs::read_dir(&dir).await {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    let mut sessions = Vec::new();
+    while let Some(entry) = entries.next_entry().await.map_err(|e| e.to_string())? {
+        let path = entry.path();
+        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+            continue;
+        }
+        // 损坏的会话文件直接跳过，不影响其他会话
+        match tokio::fs::read(&path).await {
+            Ok(data) => match serde_json::from_slice::<UploadSession>(&data) {
+                Ok(session) => sessions.push(session),
+                Err(e) => println!("跳过无法解析的上传会话 {:?}：{}", path, e),
+            },
+            Err(e) => println!("跳过无法读取的上传会话 {:?}：{}", path, e),
+        }
+    }
+
+    sessions.sort_by_key(|s| s.created_at);
+    Ok(sessions)
+}
+
+pub async fn remove(app: &AppHandle, file_id: &str) -> Result<(), String> {
+    let path = session_path(app, file_id)?;
+    match tokio::fs::remove_file(&path).await {
+        Ok(_) => Ok(()),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// 以文件大小和修改时间作为指纹，判断本地文件在上次上传后是否被改动
+pub async fn fingerprint(path: &str) -> Result<FileFingerprint, String> {
+    let metadata = tokio::fs::metadata(path).await.map_err(|e| e.to_string())?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default();
+
+    Ok(FileFingerprint {
+        size: metadata.len(),
+        modified,
+    })
+}
@@ -0,0 +1,85 @@
+use crate::r2::R2Client;
+use crate::typ::UploadError;
+use dashmap::DashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{watch, OwnedSemaphorePermit};
+use tokio::task::JoinHandle;
+
+// 放在 Tauri 托管状态中的上传任务表，键都是 file_id
+// 任务结束或取消时移除全部条目，不再持有客户端
+#[derive(Default)]
+pub struct UploadTasks {
+    // 上传任务的 JoinHandle，用于取消
+    handles: DashMap<String, JoinHandle<Result<(), UploadError>>>,
+    // 进行中的分段上传，用于取消时中止
+    multipart: DashMap<String, MultipartUpload>,
+    // 暂停开关和调度器的文件许可
+    controls: DashMap<String, Arc<UploadControl>>,
+}
+
+pub struct MultipartUpload {
+    pub client: Arc<R2Client>,
+    pub remote_filename: String,
+    pub upload_id: String,
+}
+
+// paused 为 true 时上传暂停，不再读取新的分段；暂停期间归还文件许可，让排队的文件先上传
+pub struct UploadControl {
+    pub paused: watch::Sender<bool>,
+    pub permit: Mutex<Option<OwnedSemaphorePermit>>,
+}
+
+impl UploadTasks {
+    // 在启动任务之前登记，使排队中的文件也能暂停
+    pub fn register(&self, file_id: &str) {
+        self.controls.insert(
+            file_id.to_string(),
+            Arc::new(UploadControl {
+                paused: watch::channel(false).0,
+                permit: Mutex::new(None),
+            }),
+        );
+    }
+
+    // 任务可能在这之前就已经结束，此时控制已被移除，不再保存 JoinHandle
+    pub fn set_handle(&self, file_id: &str, handle: JoinHandle<Result<(), UploadError>>) {
+        if let Some(_control) = self.controls.get(file_id) {
+            self.handles.insert(file_id.to_string(), handle);
+        }
+    }
+
+    pub fn set_multipart(&self, file_id: &str, upload: MultipartUpload) {
+        self.multipart.insert(file_id.to_string(), upload);
+    }
+
+    pub fn control(&self, file_id: &str) -> Option<Arc<UploadControl>> {
+        self.controls.get(file_id).map(|control| control.clone())
+    }
+
+    pub fn set_paused(&self, file_id: &str, paused: bool) {
+        if let Some(control) = self.controls.get(file_id) {
+            control.paused.send_replace(paused);
+        }
+    }
+
+    pub fn is_paused(&self, file_id: &str) -> bool {
+        self.controls
+            .get(file_id)
+            .is_some_and(|control| *control.paused.borrow())
+    }
+
+    // 任务结束时调用，先移除控制，之后 set_handle 不会再写入
+    pub fn finish(&self, file_id: &str) {
+        self.controls.remove(file_id);
+        self.handles.remove(file_id);
+        self.multipart.remove(file_id);
+    }
+
+    // 取消时中止任务，任务已经结束时返回 None；否则返回需要中止的分段上传
+    pub fn cancel(&self, file_id: &str) -> Option<Option<MultipartUpload>> {
+        let (_, handle) = self.handles.remove(file_id)?;
+        handle.abort();
+        self.controls.remove(file_id);
+        Some(self.multipart.remove(file_id).map(|(_, upload)| upload))
+    }
+}
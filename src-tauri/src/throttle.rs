@@ -0,0 +1,245 @@
+use crate::typ::{BandwidthLimit, BandwidthRule};
+use aws_sdk_s3::primitives::{ByteStream, SdkBody};
+use bytes::Bytes;
+use chrono::{Local, NaiveTime};
+use dashmap::DashMap;
+use hyper::body::{HttpBody, SizeHint};
+use hyper::HeaderMap;
+use once_cell::sync::Lazy;
+use std::convert::Infallible;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex, RwLock};
+use std::task::{ready, Context, Poll};
+use std::time::Duration;
+use tokio::time::{Instant, Sleep};
+
+// 全局限速配置，由前端的应用设置同步过来
+static GLOBAL_LIMIT: Lazy<RwLock<BandwidthLimit>> = Lazy::new(Default::default);
+static GLOBAL_LIMITER: Lazy<RateLimiter> = Lazy::new(RateLimiter::new);
+// 键是存储桶的 endpoint/bucket_name，同一个存储桶的所有批次共享一个令牌桶
+static BUCKET_LIMITERS: Lazy<DashMap<String, Arc<RateLimiter>>> = Lazy::new(DashMap::new);
+
+pub fn set_global_limit(limit: BandwidthLimit) {
+    *GLOBAL_LIMIT.write().unwrap() = limit;
+}
+
+// 请求体按小块发送，每块发出前才取令牌，避免整段数据取完令牌后一次性突发
+const CHUNK_SIZE: usize = 64 * 1024;
+
+// 全局和存储桶限速中当前生效的较低速率，None 表示不限速
+pub fn current_rate(bucket_limit: Option<&BandwidthLimit>) -> Option<u64> {
+    let global_rate = GLOBAL_LIMIT.read().unwrap().current_rate();
+    let bucket_rate = bucket_limit.and_then(BandwidthLimit::current_rate);
+    match (global_rate, bucket_rate) {
+        (Some(a), Some(b)) => Some(a.min(b)),
+        (rate, None) | (None, rate) => rate,
+    }
+}
+
+// 上传请求的请求体；当前没有生效的限速时直接使用内存中的数据
+pub fn body(bucket_key: &str, bucket_limit: Option<&BandwidthLimit>, data: Bytes) -> ByteStream {
+    if current_rate(bucket_limit).is_none() {
+        return ByteStream::from(data);
+    }
+
+    let bucket_key: Arc<str> = Arc::from(bucket_key);
+    let bucket_limit = bucket_limit.cloned().map(Arc::new);
+    // SDK 重试时重新构造请求体，从头开始限速发送
+    ByteStream::new(SdkBody::retryable(move || {
+        SdkBody::from_body_0_4(ThrottledBody {
+            data: data.clone(),
+            bucket_key: bucket_key.clone(),
+            bucket_limit: bucket_limit.clone(),
+            sleep: None,
+        })
+    }))
+}
+
+// 发送 bytes 字节之前需要等待的时间，全局令牌桶和存储桶自己的令牌桶都要满足
+fn delay(bucket_key: &str, bucket_limit: Option<&BandwidthLimit>, bytes: usize) -> Duration {
+    let global_rate = GLOBAL_LIMIT.read().unwrap().current_rate();
+    let mut wait = GLOBAL_LIMITER.reserve(bytes, global_rate);
+
+    if let Some(rate) = bucket_limit.and_then(BandwidthLimit::current_rate) {
+        let limiter = BUCKET_LIMITERS
+            .entry(bucket_key.to_string())
+            .or_insert_with(|| Arc::new(RateLimiter::new()))
+            .clone();
+        wait = wait.max(limiter.reserve(bytes, Some(rate)));
+    }
+    wait
+}
+
+struct ThrottledBody {
+    data: Bytes,
+    bucket_key: Arc<str>,
+    bucket_limit: Option<Arc<BandwidthLimit>>,
+    // 下一块数据发出前的等待
+    sleep: Option<Pin<Box<Sleep>>>,
+}
+
+impl HttpBody for ThrottledBody {
+    type Data = Bytes;
+    type Error = Infallible;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Infallible>>> {
+        let this = &mut *self;
+        if this.data.is_empty() {
+            return Poll::Ready(None);
+        }
+
+        let len = this.data.len().min(CHUNK_SIZE);
+        let sleep = this.sleep.get_or_insert_with(|| {
+            Box::pin(tokio::time::sleep(delay(
+                &this.bucket_key,
+                this.bucket_limit.as_deref(),
+                len,
+            )))
+        });
+        ready!(sleep.as_mut().poll(cx));
+        this.sleep = None;
+        Poll::Ready(Some(Ok(this.data.split_to(len))))
+    }
+
+    fn poll_trailers(
+        self: Pin<&mut Self>,
+        _cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Infallible>> {
+        Poll::Ready(Ok(None))
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.data.is_empty()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.data.len() as u64)
+    }
+}
+
+impl BandwidthLimit {
+    // 当前时刻生效的速率（字节/秒），None 表示不限速
+    fn current_rate(&self) -> Option<u64> {
+        let now = Local::now().time();
+        self.schedule
+            .iter()
+            .find(|rule| rule.contains(now))
+            .map_or(self.bytes_per_sec, |rule| rule.bytes_per_sec)
+            .filter(|rate| *rate > 0)
+    }
+}
+
+impl BandwidthRule {
+    // start..end 左闭右开，end 早于 start 时表示跨越午夜
+    fn contains(&self, time: NaiveTime) -> bool {
+        let (Ok(start), Ok(end)) = (
+            NaiveTime::parse_from_str(&self.start, "%H:%M"),
+            NaiveTime::parse_from_str(&self.end, "%H:%M"),
+        ) else {
+            return false;
+        };
+
+        if start <= end {
+            start <= time && time < end
+        } else {
+            time >= start || time < end
+        }
+    }
+}
+
+// 令牌桶，容量为 1 秒的流量；令牌不足时允许透支，调用方按透支量等待
+struct RateLimiter {
+    state: Mutex<(f64, Instant)>,
+}
+
+impl RateLimiter {
+    fn new() -> Self {
+        Self {
+            state: Mutex::new((0.0, Instant::now())),
+        }
+    }
+
+    // 预先扣除 bytes 个令牌，返回需要等待的时间
+    fn reserve(&self, bytes: usize, rate: Option<u64>) -> Duration {
+        let Some(rate) = rate else {
+            return Duration::ZERO;
+        };
+        let rate = rate as f64;
+
+        let mut state = self.state.lock().unwrap();
+        let (tokens, last) = &mut *state;
+        let now = Instant::now();
+        *tokens = (*tokens + now.duration_since(*last).as_secs_f64() * rate).min(rate);
+        *last = now;
+        *tokens -= bytes as f64;
+        if *tokens < 0.0 {
+            Duration::from_secs_f64(-*tokens / rate)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(start: &str, end: &str) -> BandwidthRule {
+        BandwidthRule {
+            start: start.to_string(),
+            end: end.to_string(),
+            bytes_per_sec: Some(1024),
+        }
+    }
+
+    fn time(value: &str) -> NaiveTime {
+        NaiveTime::parse_from_str(value, "%H:%M").unwrap()
+    }
+
+    #[test]
+    fn contains_is_half_open() {
+        let rule = rule("09:00", "18:00");
+        assert!(rule.contains(time("09:00")));
+        assert!(rule.contains(time("17:59")));
+        assert!(!rule.contains(time("18:00")));
+        assert!(!rule.contains(time("08:59")));
+    }
+
+    #[test]
+    fn contains_wraps_past_midnight() {
+        let rule = rule("22:00", "06:00");
+        assert!(rule.contains(time("22:00")));
+        assert!(rule.contains(time("23:59")));
+        assert!(rule.contains(time("00:00")));
+        assert!(rule.contains(time("05:59")));
+        assert!(!rule.contains(time("06:00")));
+        assert!(!rule.contains(time("12:00")));
+    }
+
+    #[test]
+    fn contains_ignores_invalid_times() {
+        assert!(!rule("9am", "18:00").contains(time("12:00")));
+        assert!(!rule("09:00", "24:00").contains(time("12:00")));
+        // 起止相同的规则不覆盖任何时刻
+        assert!(!rule("09:00", "09:00").contains(time("09:00")));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn reserve_waits_for_overdrawn_tokens() {
+        let limiter = RateLimiter::new();
+        assert_eq!(limiter.reserve(1000, None), Duration::ZERO);
+
+        assert_eq!(limiter.reserve(1000, Some(1000)), Duration::from_secs(1));
+        tokio::time::advance(Duration::from_millis(500)).await;
+        assert_eq!(limiter.reserve(500, Some(1000)), Duration::from_secs(1));
+
+        // 空闲期间积累的令牌最多为 1 秒的流量
+        tokio::time::advance(Duration::from_secs(5)).await;
+        assert_eq!(limiter.reserve(1000, Some(1000)), Duration::ZERO);
+        assert_eq!(limiter.reserve(500, Some(1000)), Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,167 @@
+use crate::checksum;
+use crate::headers::non_empty;
+use crate::typ::TlsOptions;
+use once_cell::sync::Lazy;
+use rustls::client::{ServerCertVerified, ServerCertVerifier, WebPkiVerifier};
+use rustls::{Certificate, ClientConfig, RootCertStore, ServerName};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::SystemTime;
+
+// 系统根证书只加载一次
+static NATIVE_ROOTS: Lazy<RootCertStore> = Lazy::new(|| {
+    let mut roots = RootCertStore::empty();
+    match rustls_native_certs::load_native_certs() {
+        Ok(certs) => {
+            let certs: Vec<Vec<u8>> = certs.into_iter().map(|cert| cert.0).collect();
+            roots.add_parsable_certificates(&certs);
+        }
+        Err(e) => println!("加载系统根证书失败：{}", e),
+    }
+    roots
+});
+
+// 系统根证书加上存储桶额外信任的 CA；设置了指纹或跳过校验时换成自定义的校验器
+pub fn client_config(options: &TlsOptions) -> Result<ClientConfig, String> {
+    let mut roots = NATIVE_ROOTS.clone();
+    if let Some(pem) = non_empty(&options.ca_certificates) {
+        for cert in parse_pem(&pem)? {
+            roots
+                .add(&cert)
+                .map_err(|e| format!("Invalid CA certificate: {}", e))?;
+        }
+    }
+
+    let pins = options
+        .pinned_sha256
+        .iter()
+        .filter(|pin| !pin.trim().is_empty())
+        .map(|pin| parse_fingerprint(pin))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let builder = ClientConfig::builder().with_safe_defaults();
+    if !options.skip_verify && pins.is_empty() {
+        return Ok(builder.with_root_certificates(roots).with_no_client_auth());
+    }
+
+    if options.skip_verify {
+        println!("警告：已跳过 TLS 证书校验，连接可能被中间人劫持，只应在本地测试时使用");
+    }
+    let verifier = Verifier {
+        inner: WebPkiVerifier::new(roots, None),
+        pins,
+        skip_verify: options.skip_verify,
+    };
+    Ok(builder
+        .with_custom_certificate_verifier(Arc::new(verifier))
+        .with_no_client_auth())
+}
+
+fn parse_pem(pem: &str) -> Result<Vec<Certificate>, String> {
+    let certs = rustls_pemfile::certs(&mut pem.as_bytes())
+        .map_err(|e| format!("Invalid CA certificate: {}", e))?;
+    if certs.is_empty() {
+        return Err("No certificate found in the CA PEM".to_string());
+    }
+    Ok(certs.into_iter().map(Certificate).collect())
+}
+
+// 接受 ab:cd:... 或 abcd... 形式的十六进制指纹
+fn parse_fingerprint(pin: &str) -> Result<[u8; 32], String> {
+    let hex: String = pin
+        .chars()
+        .filter(|c| *c != ':' && !c.is_ascii_whitespace())
+        .collect();
+    let invalid = || format!("Invalid SHA-256 certificate fingerprint: {}", pin);
+    // from_str_radix 会接受 + 号，这里先确认全是十六进制字符
+    if hex.len() != 64 || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(invalid());
+    }
+
+    let mut fingerprint = [0u8; 32];
+    for (i, byte) in fingerprint.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(fingerprint)
+}
+
+// 指纹匹配服务器证书时直接信任（用于自签名证书），不匹配时拒绝；都未设置时按证书链校验
+struct Verifier {
+    inner: WebPkiVerifier,
+    pins: Vec<[u8; 32]>,
+    skip_verify: bool,
+}
+
+impl ServerCertVerifier for Verifier {
+    fn verify_server_cert(
+        &self,
+        end_entity: &Certificate,
+        intermediates: &[Certificate],
+        server_name: &ServerName,
+        scts: &mut dyn Iterator<Item = &[u8]>,
+        ocsp_response: &[u8],
+        now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        if self.skip_verify {
+            return Ok(ServerCertVerified::assertion());
+        }
+        if self.pins.is_empty() {
+            return self.inner.verify_server_cert(
+                end_entity,
+                intermediates,
+                server_name,
+                scts,
+                ocsp_response,
+                now,
+            );
+        }
+
+        let fingerprint = Sha256::digest(&end_entity.0);
+        if self.pins.iter().any(|pin| pin[..] == fingerprint[..]) {
+            Ok(ServerCertVerified::assertion())
+        } else {
+            Err(rustls::Error::General(format!(
+                "Server certificate fingerprint {} does not match the pinned fingerprint",
+                checksum::hex(&fingerprint)
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HEX: &str = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+
+    fn expected() -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        for (i, byte) in bytes.iter_mut().enumerate() {
+            *byte = u8::from_str_radix(&HEX[i * 2..i * 2 + 2], 16).unwrap();
+        }
+        bytes
+    }
+
+    #[test]
+    fn parse_fingerprint_accepts_plain_and_colon_separated_hex() {
+        assert_eq!(parse_fingerprint(HEX).unwrap(), expected());
+        assert_eq!(parse_fingerprint(&HEX.to_uppercase()).unwrap(), expected());
+
+        let pairs = (0..32).map(|i| &HEX[i * 2..i * 2 + 2]).collect::<Vec<_>>();
+        let colons = format!(" {} \n", pairs.join(":"));
+        assert_eq!(parse_fingerprint(&colons).unwrap(), expected());
+        assert_eq!(parse_fingerprint(&pairs.join(" ")).unwrap(), expected());
+    }
+
+    #[test]
+    fn parse_fingerprint_rejects_invalid_input() {
+        assert!(parse_fingerprint(&HEX[..62]).is_err());
+        assert!(parse_fingerprint(&format!("{}00", HEX)).is_err());
+        assert!(parse_fingerprint(&format!("zz{}", &HEX[2..])).is_err());
+        assert!(parse_fingerprint(&format!("+a{}", &HEX[2..])).is_err());
+        assert!(parse_fingerprint(&format!("é{}", &HEX[2..])).is_err());
+
+        let err = parse_fingerprint("abc").unwrap_err();
+        assert_eq!(err, "Invalid SHA-256 certificate fingerprint: abc");
+    }
+}
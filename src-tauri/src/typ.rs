@@ -1,10 +1,226 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use zeroize::Zeroize;
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub enum UploadSource {
     FilePath(String),
+    // 文本，或 data:image/png;base64,... 形式的 data URL
     FileContent(String),
+    // base64 编码的二进制内容
+    Base64(String),
+    // 由后端直接下载 HTTP(S) 资源并流式上传，headers 可用于鉴权，max_bytes 限制下载大小
+    #[serde(rename_all = "camelCase")]
+    Url {
+        url: String,
+        #[serde(default)]
+        headers: HashMap<String, String>,
+        #[serde(default)]
+        max_bytes: Option<u64>,
+    },
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum BucketType {
+    #[default]
+    R2,
+    S3,
+}
+
+// R2 存储桶所在的管辖区，决定 endpoint 的主机名
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Jurisdiction {
+    #[default]
+    Default,
+    Eu,
+    Fedramp,
+}
+
+// 分段上传失败时的重试策略，按存储桶配置
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RetryConfig {
+    pub max_retries: u32,
+    pub base_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_retries: 5,
+            base_delay_ms: 500,
+            max_delay_ms: 30_000,
+        }
+    }
+}
+
+// 超时设置（秒），按存储桶配置，为空表示不限制
+// read 是发出请求后等待响应的时间；分段上传按分段大小和观测到的速率另行计算，不受 read 和 attempt 限制
+#[derive(Debug, Serialize, Deserialize, Clone, Copy)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Timeouts {
+    pub connect_secs: Option<u64>,
+    pub read_secs: Option<u64>,
+    // 整个操作，包括 SDK 自带的重试
+    pub operation_secs: Option<u64>,
+    // 单次尝试
+    pub operation_attempt_secs: Option<u64>,
+}
+
+impl Default for Timeouts {
+    fn default() -> Self {
+        Self {
+            connect_secs: Some(30),
+            read_secs: Some(30),
+            operation_secs: None,
+            operation_attempt_secs: None,
+        }
+    }
+}
+
+// 上传限速，bytes_per_sec 为空表示不限速；schedule 中第一条覆盖当前时刻（本地时间 HH:MM）的规则优先
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BandwidthLimit {
+    pub bytes_per_sec: Option<u64>,
+    pub schedule: Vec<BandwidthRule>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BandwidthRule {
+    pub start: String,
+    pub end: String,
+    pub bytes_per_sec: Option<u64>,
+}
+
+// 上传时附带的校验和算法，none 表示不计算
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum Checksum {
+    #[default]
+    None,
+    Sha256,
+    Crc32c,
+}
+
+// 代理模式：none 直连，system 使用系统代理，manual 使用 url 指定的代理
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProxyMode {
+    None,
+    #[default]
+    System,
+    Manual,
+}
+
+// url 形如 http://host:port、https://host:port 或 socks5://host:port
+// no_proxy 中的主机直连，支持 example.com、.example.com、*.example.com 和 *
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ProxyConfig {
+    pub mode: ProxyMode,
+    pub url: Option<String>,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub no_proxy: Vec<String>,
+}
+
+// 前端 Bucket 的后端表示，type 为 s3 时使用 endpoint/region/forcePathStyle 连接任意 S3 兼容服务
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct Bucket {
+    #[serde(rename = "type", default)]
+    pub bucket_type: BucketType,
+    pub bucket_name: String,
+    #[serde(default)]
+    pub account_id: String,
+    pub access_key: String,
+    pub secret_key: String,
+    #[serde(default)]
+    pub custom_domain: Option<String>,
+    #[serde(default)]
+    pub jurisdiction: Jurisdiction,
+    #[serde(default)]
+    pub endpoint: Option<String>,
+    #[serde(default)]
+    pub region: Option<String>,
+    #[serde(default)]
+    pub force_path_style: bool,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub timeouts: Timeouts,
+    // 分段大小（MiB），为空时根据文件大小自动选择
+    #[serde(default)]
+    pub part_size_mb: Option<u64>,
+    #[serde(default)]
+    pub bandwidth_limit: Option<BandwidthLimit>,
+    #[serde(default)]
+    pub checksum: Checksum,
+    #[serde(default)]
+    pub header_rules: Vec<HeaderRule>,
+    #[serde(default)]
+    pub tls: TlsOptions,
+}
+
+// 密钥只在后端的保险库和使用它的请求中出现，用完即清零
+impl Drop for Bucket {
+    fn drop(&mut self) {
+        self.access_key.zeroize();
+        self.secret_key.zeroize();
+    }
+}
+
+// 自建服务（如内网 MinIO）的 TLS 设置
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct TlsOptions {
+    // 额外信任的 CA 证书，PEM 格式，可以包含多个证书
+    pub ca_certificates: Option<String>,
+    // 服务器证书的 SHA-256 指纹（十六进制，可带冒号），匹配任意一个即信任，不再校验证书链
+    pub pinned_sha256: Vec<String>,
+    // 跳过证书校验，仅用于本地测试
+    pub skip_verify: bool,
+}
+
+// 远端已存在同名对象时的处理方式，按批次指定
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ConflictPolicy {
+    #[default]
+    Overwrite,
+    Skip,
+    // 改名为 name (1).ext、name (2).ext ...
+    Rename,
+    // 大小和 ETag/校验和一致时跳过，否则覆盖
+    SkipIdentical,
+}
+
+// 上传时附带的 HTTP 头和 x-amz-meta-* 元数据，可按批次设置，也可按文件覆盖
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ObjectHeaders {
+    pub content_type: Option<String>,
+    pub cache_control: Option<String>,
+    pub content_disposition: Option<String>,
+    pub content_encoding: Option<String>,
+    pub content_language: Option<String>,
+    // HTTP 日期格式，例如 Wed, 21 Oct 2015 07:28:00 GMT
+    pub expires: Option<String>,
+    pub metadata: HashMap<String, String>,
+}
+
+// 按存储桶保存的请求头规则，pattern 不含 / 时匹配文件名（如 *.wasm），否则匹配完整的远端路径（如 assets/**）
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct HeaderRule {
+    pub pattern: String,
+    pub headers: ObjectHeaders,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -13,6 +229,8 @@ pub struct File {
     pub id: String,
     pub source: UploadSource,
     pub remote_filename: String,
+    #[serde(default)]
+    pub headers: Option<ObjectHeaders>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -27,8 +245,12 @@ pub struct FileDetail {
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub enum UploadStatus {
+    Queued,
     Success,
     Cancelled,
+    Skipped {
+        reason: String,
+    },
     Uploading {
         progress: f64,
         #[serde(rename = "bytesUploaded")]
@@ -37,12 +259,227 @@ pub enum UploadStatus {
         total_bytes: u64,
         speed: f64,
     },
+    Paused {
+        progress: f64,
+        #[serde(rename = "bytesUploaded")]
+        bytes_uploaded: u64,
+        #[serde(rename = "totalBytes")]
+        total_bytes: u64,
+    },
     Error {
         message: String,
         code: String,
     },
 }
 
+// 上传失败的原因，code 原样作为 UploadStatus::Error 的 code 发给前端
+#[derive(Debug, Clone)]
+pub struct UploadError {
+    pub code: &'static str,
+    pub message: String,
+}
+
+impl UploadError {
+    pub const UPLOAD_ERROR: &'static str = "UPLOAD_ERROR";
+    pub const CHECKSUM_MISMATCH: &'static str = "CHECKSUM_MISMATCH";
+
+    pub fn new(code: &'static str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<String> for UploadError {
+    fn from(message: String) -> Self {
+        Self::new(Self::UPLOAD_ERROR, message)
+    }
+}
+
+impl std::fmt::Display for UploadError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+// 远端对象列表的查询条件；delimiter 为空时递归列出 prefix 下的所有对象
+// search 不为空时在 prefix 下递归查找 key 包含该字符串（不区分大小写）的对象
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct ListOptions {
+    pub prefix: Option<String>,
+    pub delimiter: Option<String>,
+    pub continuation_token: Option<String>,
+    pub max_keys: Option<i32>,
+    pub search: Option<String>,
+    pub sort_by: ObjectSortKey,
+    pub descending: bool,
+    // 为每个对象额外发一次 HEAD 取 Content-Type，对象多时较慢，默认关闭
+    pub include_content_type: bool,
+}
+
+// 只对当前页排序，服务端始终按 key 的字典序返回
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ObjectSortKey {
+    #[default]
+    Key,
+    Size,
+    LastModified,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectEntry {
+    pub key: String,
+    pub size: u64,
+    // 毫秒时间戳
+    pub last_modified: Option<u64>,
+    pub e_tag: Option<String>,
+    pub content_type: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ObjectPage {
+    // delimiter 折叠出的“文件夹”，即 CommonPrefixes
+    pub folders: Vec<String>,
+    pub objects: Vec<ObjectEntry>,
+    pub next_continuation_token: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteFailure {
+    pub key: String,
+    pub code: String,
+    pub message: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteResult {
+    pub deleted: u64,
+    pub failed: Vec<DeleteFailure>,
+}
+
+// 删除进度，通过 delete-progress 事件发给前端；按前缀删除时 total 未知
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DeleteProgress {
+    pub task_id: String,
+    pub deleted: u64,
+    pub failed: u64,
+    pub total: Option<u64>,
+    pub done: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyItem {
+    pub source_key: String,
+    pub target_key: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyFailure {
+    pub source_key: String,
+    pub target_key: String,
+    pub message: String,
+}
+
+// 移动时 deleted/delete_failed 记录源对象的删除结果，有复制失败时不删除任何源对象
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyResult {
+    pub copied: u64,
+    pub failed: Vec<CopyFailure>,
+    pub deleted: u64,
+    pub delete_failed: Vec<DeleteFailure>,
+}
+
+// 复制进度，通过 copy-progress 事件发给前端
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CopyProgress {
+    pub task_id: String,
+    pub copied: u64,
+    pub failed: u64,
+    pub total: u64,
+    pub done: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub enum DownloadStatus {
+    Queued,
+    Downloading {
+        progress: f64,
+        #[serde(rename = "bytesDownloaded")]
+        bytes_downloaded: u64,
+        #[serde(rename = "totalBytes")]
+        total_bytes: u64,
+        speed: f64,
+        // 预计剩余秒数，速度未知时为空
+        eta: Option<f64>,
+    },
+    Success,
+    Error {
+        message: String,
+    },
+}
+
+// 下载进度，通过 download-progress 事件发给前端
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadProgress {
+    pub task_id: String,
+    pub key: String,
+    pub path: String,
+    pub status: DownloadStatus,
+    pub timestamp: u64,
+}
+
+// 与 .part 文件放在一起的下载进度，ETag 或大小变化时作废重新下载
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DownloadState {
+    pub e_tag: String,
+    pub size: u64,
+    pub chunk_size: u64,
+    pub completed_chunks: Vec<u64>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PresignMethod {
+    #[default]
+    Get,
+    Put,
+}
+
+// 预签名 URL 的参数；response_content_disposition 只对 GET 有效，content_type 只对 PUT 有效
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PresignOptions {
+    pub method: PresignMethod,
+    pub expires_in_secs: Option<u64>,
+    pub response_content_disposition: Option<String>,
+    pub content_type: Option<String>,
+}
+
+// headers 是使用 URL 时必须原样带上的请求头
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PresignedUrl {
+    pub url: String,
+    pub method: String,
+    pub headers: HashMap<String, String>,
+    pub expires_at: u64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct UploadHistory {
@@ -52,3 +489,71 @@ pub struct UploadHistory {
     pub status: UploadStatus,
     pub timestamp: u64,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct FileFingerprint {
+    pub size: u64,
+    pub modified: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SessionPart {
+    pub part_number: i32,
+    pub e_tag: String,
+    #[serde(default)]
+    pub checksum: Option<String>,
+}
+
+// 持久化到磁盘的分段上传会话，不包含任何密钥
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadSession {
+    pub file_id: String,
+    pub bucket_name: String,
+    pub endpoint: String,
+    pub domain: String,
+    pub path: String,
+    pub remote_filename: String,
+    pub upload_id: String,
+    pub part_size: u64,
+    #[serde(default)]
+    pub checksum: Checksum,
+    #[serde(default)]
+    pub file_checksum: Option<String>,
+    pub fingerprint: FileFingerprint,
+    pub completed_parts: Vec<SessionPart>,
+    pub created_at: u64,
+}
+
+// 启动时提示续传的会话，bucket_id 为空表示保险库中已没有对应的存储桶
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingUpload {
+    pub bucket_id: Option<u64>,
+    #[serde(flatten)]
+    pub session: UploadSession,
+}
+
+// 保险库的密钥来源：machine 由机器标识派生，password 由主密码派生
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum VaultKeySource {
+    #[default]
+    Machine,
+    Password,
+}
+
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct VaultStatus {
+    pub locked: bool,
+    pub key_source: VaultKeySource,
+    // 保险库文件是否已创建，未创建时解锁会用输入的主密码新建
+    pub initialized: bool,
+    // 移动端等取不到机器标识的平台只能使用主密码
+    pub machine_key_available: bool,
+    // 打开或解锁保险库失败的原因，例如文件损坏、版本不支持或机器标识变化
+    pub error: Option<String>,
+}
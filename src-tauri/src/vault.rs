@@ -0,0 +1,509 @@
+use crate::atomic_file;
+use crate::connector;
+use crate::registry::ClientRegistry;
+use crate::typ::{Bucket, VaultKeySource, VaultStatus};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::pbkdf2;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use tauri::{AppHandle, Emitter, Manager, State};
+use zeroize::{Zeroize, Zeroizing};
+
+const VAULT_VERSION: u32 = 1;
+const SALT_LEN: usize = 16;
+// 主密码可能较弱，迭代次数按 OWASP 对 PBKDF2-HMAC-SHA256 的建议；机器标识本身不是秘密，少量迭代即可
+const PASSWORD_ITERATIONS: u32 = 600_000;
+const MACHINE_ITERATIONS: u32 = 10_000;
+
+// 存储桶配置和密钥保存在 {app_data_dir}/vault.json，使用 AES-256-GCM 加密
+// 密钥由主密码或机器标识经 PBKDF2 派生，每次保存都重新生成 nonce
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct VaultFile {
+    version: u32,
+    key_source: VaultKeySource,
+    salt: String,
+    iterations: u32,
+    nonce: String,
+    ciphertext: String,
+}
+
+// 放在 Tauri 托管状态中，命令通过存储桶 id 取出配置，前端不再持有密钥
+pub struct Vault {
+    path: PathBuf,
+    state: RwLock<VaultState>,
+}
+
+struct VaultState {
+    key_source: VaultKeySource,
+    salt: [u8; SALT_LEN],
+    iterations: u32,
+    // 为空表示尚未解锁
+    key: Option<Zeroizing<[u8; 32]>>,
+    data: VaultData,
+    error: Option<String>,
+}
+
+// 加密保存的内容：存储桶配置和代理密码
+#[derive(Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct VaultData {
+    buckets: BTreeMap<u64, Bucket>,
+    #[serde(default)]
+    proxy_password: Option<String>,
+}
+
+impl Drop for VaultData {
+    fn drop(&mut self) {
+        self.proxy_password.zeroize();
+    }
+}
+
+impl Vault {
+    // 使用机器标识的保险库打开时自动解锁，使用主密码的需要调用 vault_unlock
+    // 打开失败不影响启动，保险库保持锁定，错误通过 vault_status 返回给前端
+    pub fn open(app: &AppHandle) -> Self {
+        let path = app
+            .path()
+            .app_data_dir()
+            .map(|dir| dir.join("vault.json"))
+            .map_err(|e| e.to_string());
+        let vault = Self {
+            path: path.clone().unwrap_or_default(),
+            state: RwLock::new(VaultState {
+                key_source: VaultKeySource::Password,
+                salt: [0; SALT_LEN],
+                iterations: PASSWORD_ITERATIONS,
+                key: None,
+                data: VaultData::default(),
+                error: None,
+            }),
+        };
+        if let Err(e) = path.and_then(|_| vault.load()) {
+            println!("打开保险库失败：{}", e);
+            vault.state.write().unwrap().error = Some(e);
+        }
+        vault
+    }
+
+    fn load(&self) -> Result<(), String> {
+        let file = match read_file(&self.path)? {
+            Some(file) => file,
+            // 首次使用：能取到机器标识时直接创建空的保险库，否则等待设置主密码
+            None => {
+                match machine_id() {
+                    Ok(id) => self.set_key(VaultKeySource::Machine, &id)?,
+                    Err(e) => println!("无法获取机器标识，需要设置主密码：{}", e),
+                }
+                return Ok(());
+            }
+        };
+
+        self.state.write().unwrap().key_source = file.key_source;
+        if file.key_source == VaultKeySource::Machine {
+            machine_id()
+                .and_then(|id| self.unlock(&id))
+                .map_err(|e| format!("Failed to unlock vault with the machine key: {}", e))?;
+        }
+        Ok(())
+    }
+
+    pub fn status(&self) -> VaultStatus {
+        let state = self.state.read().unwrap();
+        VaultStatus {
+            locked: state.key.is_none(),
+            key_source: state.key_source,
+            initialized: self.path.is_file(),
+            machine_key_available: machine_id().is_ok(),
+            error: state.error.clone(),
+        }
+    }
+
+    // 忘记主密码、机器标识变化或文件损坏时删除保险库并重新创建，已保存的密钥全部丢失
+    pub fn reset(&self) -> Result<(), String> {
+        {
+            let mut state = self.state.write().unwrap();
+            match std::fs::remove_file(&self.path) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.to_string()),
+            }
+            state.key_source = VaultKeySource::Password;
+            state.salt = [0; SALT_LEN];
+            state.iterations = PASSWORD_ITERATIONS;
+            state.key = None;
+            state.data = VaultData::default();
+            state.error = None;
+        }
+        connector::set_proxy_password(None);
+        self.load()
+    }
+
+    // 返回的配置包含密钥，用完后随 Bucket 的 drop 清零
+    pub fn bucket(&self, bucket_id: u64) -> Result<Bucket, String> {
+        let state = self.state.read().unwrap();
+        if state.key.is_none() {
+            return Err("Vault is locked".to_string());
+        }
+        state
+            .data
+            .buckets
+            .get(&bucket_id)
+            .cloned()
+            .ok_or_else(|| format!("Bucket {} not found in vault", bucket_id))
+    }
+
+    // 按条件查找存储桶 id，保险库锁定时找不到任何存储桶
+    pub fn find_bucket(&self, predicate: impl Fn(&Bucket) -> bool) -> Option<u64> {
+        let state = self.state.read().unwrap();
+        state.key.as_ref()?;
+        state
+            .data
+            .buckets
+            .iter()
+            .find(|(_, bucket)| predicate(bucket))
+            .map(|(id, _)| *id)
+    }
+
+    // 前端编辑时不会回填密钥，留空的密钥沿用已保存的值
+    pub fn save_bucket(&self, bucket_id: u64, mut bucket: Bucket) -> Result<(), String> {
+        let mut state = self.state.write().unwrap();
+        if state.key.is_none() {
+            return Err("Vault is locked".to_string());
+        }
+        if let Some(existing) = state.data.buckets.get(&bucket_id) {
+            if bucket.access_key.is_empty() {
+                bucket.access_key = existing.access_key.clone();
+            }
+            if bucket.secret_key.is_empty() {
+                bucket.secret_key = existing.secret_key.clone();
+            }
+        }
+        if bucket.access_key.is_empty() || bucket.secret_key.is_empty() {
+            return Err("Access key and secret key are required".to_string());
+        }
+
+        state.data.buckets.insert(bucket_id, bucket);
+        self.persist(&state)
+    }
+
+    pub fn delete_bucket(&self, bucket_id: u64) -> Result<(), String> {
+        let mut state = self.state.write().unwrap();
+        if state.key.is_none() {
+            return Err("Vault is locked".to_string());
+        }
+        if state.data.buckets.remove(&bucket_id).is_some() {
+            self.persist(&state)?;
+        }
+        Ok(())
+    }
+
+    // password 为空时删除已保存的代理密码，保存后同步给代理设置
+    pub fn set_proxy_password(&self, password: Option<String>) -> Result<(), String> {
+        let mut state = self.state.write().unwrap();
+        if state.key.is_none() {
+            return Err("Vault is locked".to_string());
+        }
+        let password = password.filter(|password| !password.is_empty());
+        let previous = std::mem::replace(&mut state.data.proxy_password, password);
+        if let Err(e) = self.persist(&state) {
+            state.data.proxy_password = previous;
+            return Err(e);
+        }
+        connector::set_proxy_password(state.data.proxy_password.clone());
+        Ok(())
+    }
+
+    // 保险库文件还不存在时，用这个密码创建新的保险库
+    fn unlock(&self, secret: &str) -> Result<(), String> {
+        let file = match read_file(&self.path)? {
+            Some(file) => file,
+            None => return self.set_key(VaultKeySource::Password, secret),
+        };
+
+        let salt = decode_salt(&file.salt)?;
+        let key = derive_key(secret, &salt, file.iterations)?;
+        let nonce = STANDARD
+            .decode(&file.nonce)
+            .map_err(|e| format!("Failed to read vault: {}", e))?;
+        let nonce = Nonce::try_assume_unique_for_key(&nonce)
+            .map_err(|_| "Failed to read vault: invalid nonce".to_string())?;
+        let mut ciphertext = STANDARD
+            .decode(&file.ciphertext)
+            .map_err(|e| format!("Failed to read vault: {}", e))?;
+        let plaintext = Zeroizing::new(
+            sealing_key(&key)?
+                .open_in_place(nonce, Aad::empty(), &mut ciphertext)
+                .map_err(|_| "Wrong password or corrupted vault".to_string())?
+                .to_vec(),
+        );
+        ciphertext.zeroize();
+        let data: VaultData = serde_json::from_slice(&plaintext)
+            .map_err(|e| format!("Failed to read vault: {}", e))?;
+
+        let mut state = self.state.write().unwrap();
+        state.key_source = file.key_source;
+        state.salt = salt;
+        state.iterations = file.iterations;
+        state.key = Some(key);
+        state.data = data;
+        state.error = None;
+        connector::set_proxy_password(state.data.proxy_password.clone());
+        Ok(())
+    }
+
+    // 更换密钥来源或主密码：重新生成盐并用新密钥加密保存
+    fn set_key(&self, key_source: VaultKeySource, secret: &str) -> Result<(), String> {
+        let mut state = self.state.write().unwrap();
+        let iterations = match key_source {
+            VaultKeySource::Machine => MACHINE_ITERATIONS,
+            VaultKeySource::Password => PASSWORD_ITERATIONS,
+        };
+        let mut salt = [0u8; SALT_LEN];
+        SystemRandom::new()
+            .fill(&mut salt)
+            .map_err(|_| "Failed to generate random salt".to_string())?;
+
+        let key = derive_key(secret, &salt, iterations)?;
+        let previous = (
+            state.key_source,
+            state.salt,
+            state.iterations,
+            state.key.take(),
+        );
+        state.key_source = key_source;
+        state.salt = salt;
+        state.iterations = iterations;
+        state.key = Some(key);
+        if let Err(e) = self.persist(&state) {
+            (state.key_source, state.salt, state.iterations, state.key) = previous;
+            return Err(e);
+        }
+        state.error = None;
+        Ok(())
+    }
+
+    fn persist(&self, state: &VaultState) -> Result<(), String> {
+        let key = state
+            .key
+            .as_ref()
+            .ok_or_else(|| "Vault is locked".to_string())?;
+        let mut nonce = [0u8; NONCE_LEN];
+        SystemRandom::new()
+            .fill(&mut nonce)
+            .map_err(|_| "Failed to generate random nonce".to_string())?;
+
+        let mut data = Zeroizing::new(serde_json::to_vec(&state.data).map_err(|e| e.to_string())?);
+        // 预留认证标签的空间，避免追加时重新分配而在旧内存里留下明文
+        data.reserve(AES_256_GCM.tag_len());
+        sealing_key(key)?
+            .seal_in_place_append_tag(
+                Nonce::assume_unique_for_key(nonce),
+                Aad::empty(),
+                &mut *data,
+            )
+            .map_err(|_| "Failed to encrypt vault".to_string())?;
+
+        let file = VaultFile {
+            version: VAULT_VERSION,
+            key_source: state.key_source,
+            salt: STANDARD.encode(state.salt),
+            iterations: state.iterations,
+            nonce: STANDARD.encode(nonce),
+            ciphertext: STANDARD.encode(&*data),
+        };
+        let json = serde_json::to_vec_pretty(&file).map_err(|e| e.to_string())?;
+
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+        }
+        atomic_file::write(&self.path, &json)
+    }
+}
+
+// 文件不存在时返回 None；内容损坏或版本不支持时返回错误
+fn read_file(path: &Path) -> Result<Option<VaultFile>, String> {
+    let data = match std::fs::read(path) {
+        Ok(data) => data,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("Failed to read vault: {}", e)),
+    };
+    let file: VaultFile =
+        serde_json::from_slice(&data).map_err(|e| format!("Failed to read vault: {}", e))?;
+    if file.version != VAULT_VERSION {
+        return Err(format!("Unsupported vault version {}", file.version));
+    }
+    Ok(Some(file))
+}
+
+fn derive_key(secret: &str, salt: &[u8], iterations: u32) -> Result<Zeroizing<[u8; 32]>, String> {
+    let iterations =
+        NonZeroU32::new(iterations).ok_or_else(|| "Invalid vault iterations".to_string())?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    pbkdf2::derive(
+        pbkdf2::PBKDF2_HMAC_SHA256,
+        iterations,
+        salt,
+        secret.as_bytes(),
+        &mut *key,
+    );
+    Ok(key)
+}
+
+fn sealing_key(key: &[u8; 32]) -> Result<LessSafeKey, String> {
+    UnboundKey::new(&AES_256_GCM, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| "Invalid vault key".to_string())
+}
+
+fn decode_salt(salt: &str) -> Result<[u8; SALT_LEN], String> {
+    STANDARD
+        .decode(salt)
+        .ok()
+        .and_then(|salt| salt.try_into().ok())
+        .ok_or_else(|| "Failed to read vault: invalid salt".to_string())
+}
+
+// 机器标识用于派生默认密钥，保险库文件被复制到其他机器后无法解密
+fn machine_id() -> Result<String, String> {
+    #[cfg(target_os = "linux")]
+    let id = ["/etc/machine-id", "/var/lib/dbus/machine-id"]
+        .iter()
+        .find_map(|path| std::fs::read_to_string(path).ok())
+        .map(|id| id.trim().to_string());
+
+    #[cfg(target_os = "macos")]
+    let id = std::process::Command::new("ioreg")
+        .args(["-rd1", "-c", "IOPlatformExpertDevice"])
+        .output()
+        .ok()
+        .and_then(|output| {
+            String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .find(|line| line.contains("IOPlatformUUID"))
+                .and_then(|line| line.split('"').nth(3))
+                .map(str::to_string)
+        });
+
+    #[cfg(target_os = "windows")]
+    let id = {
+        use std::os::windows::process::CommandExt;
+        // CREATE_NO_WINDOW，避免弹出控制台窗口
+        std::process::Command::new("reg")
+            .args([
+                "query",
+                r"HKLM\SOFTWARE\Microsoft\Cryptography",
+                "/v",
+                "MachineGuid",
+            ])
+            .creation_flags(0x08000000)
+            .output()
+            .ok()
+            .and_then(|output| {
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .find(|line| line.contains("MachineGuid"))
+                    .and_then(|line| line.split_whitespace().last())
+                    .map(str::to_string)
+            })
+    };
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    let id: Option<String> = None;
+
+    id.filter(|id| !id.is_empty())
+        .ok_or_else(|| "Machine id is not available".to_string())
+}
+
+#[tauri::command]
+pub async fn vault_status(vault: State<'_, Vault>) -> Result<VaultStatus, String> {
+    Ok(vault.status())
+}
+
+// 主密码的 PBKDF2 迭代次数很多，派生密钥放到阻塞线程中执行，避免占住异步运行时的工作线程
+async fn blocking<T: Send + 'static>(
+    app: AppHandle,
+    f: impl FnOnce(&Vault) -> Result<T, String> + Send + 'static,
+) -> Result<T, String> {
+    tokio::task::spawn_blocking(move || f(&app.state::<Vault>()))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+// 解锁后通知前端，前端收到后迁移旧版本保存在 IndexedDB 中的明文密钥
+fn emit_unlocked(app: &AppHandle) {
+    if let Err(e) = app.emit("vault-unlocked", ()) {
+        println!("发送保险库解锁事件失败：{}", e);
+    }
+}
+
+#[tauri::command]
+pub async fn vault_unlock(app: AppHandle, password: String) -> Result<(), String> {
+    let password = Zeroizing::new(password);
+    blocking(app.clone(), move |vault| vault.unlock(&password)).await?;
+    emit_unlocked(&app);
+    Ok(())
+}
+
+// 设置主密码，password 为空时改回使用机器标识派生的密钥；需要先解锁
+#[tauri::command]
+pub async fn vault_set_password(app: AppHandle, password: Option<String>) -> Result<(), String> {
+    let password = password.map(Zeroizing::new);
+    blocking(app, move |vault| {
+        if vault.status().locked {
+            return Err("Vault is locked".to_string());
+        }
+        match password {
+            Some(password) if !password.is_empty() => {
+                vault.set_key(VaultKeySource::Password, &password)
+            }
+            _ => vault.set_key(VaultKeySource::Machine, &Zeroizing::new(machine_id()?)),
+        }
+    })
+    .await
+}
+
+// 重置后能用机器标识创建新的保险库时直接解锁
+#[tauri::command]
+pub async fn vault_reset(app: AppHandle, vault: State<'_, Vault>) -> Result<(), String> {
+    vault.reset()?;
+    if !vault.status().locked {
+        emit_unlocked(&app);
+    }
+    Ok(())
+}
+
+// 代理密码只保存在保险库中，不写入前端的应用设置
+#[tauri::command]
+pub async fn vault_set_proxy_password(
+    vault: State<'_, Vault>,
+    password: Option<String>,
+) -> Result<(), String> {
+    vault.set_proxy_password(password)
+}
+
+#[tauri::command]
+pub async fn vault_save_bucket(
+    vault: State<'_, Vault>,
+    bucket_id: u64,
+    bucket: Bucket,
+) -> Result<(), String> {
+    vault.save_bucket(bucket_id, bucket)
+}
+
+#[tauri::command]
+pub async fn vault_delete_bucket(
+    vault: State<'_, Vault>,
+    registry: State<'_, ClientRegistry>,
+    bucket_id: u64,
+) -> Result<(), String> {
+    vault.delete_bucket(bucket_id)?;
+    registry.remove(bucket_id);
+    Ok(())
+}
@@ -33,7 +33,7 @@ export let en = $state({
 
 1. Click the "Check" button to verify your bucket connectivity
 2. If the connection test passes, click "Save" to store your configuration`,
-    title: "Add Cloudflare R2 Bucket",
+    title: "Add Bucket",
     cancel: "Cancel",
     save: "Save",
     addNew: "Add New Bucket",
@@ -47,6 +47,43 @@ export let en = $state({
       accessKey: "Access Key",
       secretKey: "Secret Key",
       customDomain: "Custom Domain, e.g. https://example.com",
+      endpoint: "Endpoint, leave empty for AWS S3",
+      region: "Region, e.g. us-east-1",
+      forcePathStyle: "Path-style URLs (MinIO and most self-hosted services)",
+      jurisdiction: "Jurisdiction",
+    },
+    jurisdictions: {
+      default: "Default",
+      eu: "EU",
+      fedramp: "FedRAMP",
+    },
+    types: {
+      r2: "Cloudflare R2",
+      s3: "S3 Compatible",
+    },
+    advanced: {
+      title: "Advanced Settings",
+      partSizeMb: "Part size (MiB)",
+      auto: "Auto",
+      checksum: "Checksum",
+      checksumNone: "None",
+      retry: "Retries",
+      maxRetries: "Max retries",
+      baseDelayMs: "Base delay (ms)",
+      maxDelayMs: "Max delay (ms)",
+      timeouts: "Timeouts (seconds)",
+      connectSecs: "Connect",
+      readSecs: "Read",
+      operationSecs: "Operation",
+      operationAttemptSecs: "Attempt",
+      bandwidth: "Upload Speed Limit",
+      headerRules: "Header Rules (JSON)",
+      headerRulesInvalid:
+        "Header rules must be a JSON array of { pattern, headers } objects",
+      tls: "TLS",
+      caCertificates: "Extra CA certificates (PEM)",
+      pinnedSha256: "Pinned certificate SHA-256 fingerprints, one per line",
+      skipVerify: "Skip certificate verification (testing only)",
     },
   },
   common: {
@@ -66,12 +103,52 @@ export let en = $state({
     uploadError: "Upload failed, please try again",
     noBucketWarning: "Please add a bucket in settings first",
   },
+  vault: {
+    title: "Credential Vault",
+    machineKey: "Keys are encrypted with a key derived from this device",
+    passwordKey: "Keys are encrypted with your master password",
+    locked: "The vault is locked, enter the master password to unlock it",
+    create:
+      "This device has no machine key, set a master password to create the vault",
+    password: "Master password",
+    confirmPassword: "Confirm master password",
+    passwordMismatch: "The passwords do not match",
+    unlock: "Unlock",
+    createVault: "Create Vault",
+    setPassword: "Set Master Password",
+    changePassword: "Change Master Password",
+    useMachineKey: "Use Device Key",
+    passwordSaved: "Master password saved",
+    machineKeySaved: "The vault now uses the device key",
+    reset: "Reset Vault",
+    resetConfirm:
+      "Resetting deletes all saved access keys and secret keys. Bucket settings are kept, but the keys must be entered again. Continue?",
+  },
+  pendingUploads: {
+    title: "Unfinished Uploads",
+    description:
+      "These uploads were interrupted last time, resume them or discard the uploaded parts",
+    resume: "Resume",
+    discard: "Discard",
+    resumeAll: "Resume All",
+    discardAll: "Discard All",
+    bucketMissing: "Bucket no longer exists",
+  },
+  bandwidth: {
+    limit: "Limit (MiB/s)",
+    unlimited: "Unlimited",
+    addRule: "Add Time Rule",
+    scheduleHint:
+      "During these hours (local time) the first matching rule replaces the limit above",
+  },
   settings: {
     buckets: "Buckets",
     language: "Language",
+    bandwidth: "Global Upload Speed Limit",
     bucketDetails: {
       bucket: "Bucket",
       accountId: "Account ID",
+      endpoint: "Endpoint",
     },
     defaultBucket: "Default Bucket",
     setDefault: "Set as Default",
@@ -122,6 +199,9 @@ export let en = $state({
       uploadComplete: "Upload Complete",
       uploadFailed: "Upload Failed:",
       cancelled: "Cancelled",
+      paused: "Paused",
+      skippedExists: "Skipped, file already exists",
+      skippedIdentical: "Skipped, identical file already exists",
       waiting: "Waiting...",
       copySuccess: "Copy Success",
       copyFailed: "Copy Failed",
@@ -131,15 +211,26 @@ export let en = $state({
       previous: "Previous",
       next: "Next",
       cancel: "Cancel",
+      pause: "Pause",
+      resume: "Resume",
+      pauseAll: "Pause All",
+      resumeAll: "Resume All",
     },
     upload: {
       globalPath: "Global path",
+      cacheControl: "Cache-Control",
       clear: "Clear",
       upload: "Upload",
       uploading: "Uploading...",
       remotePath: "Remote path",
       remoteFilename: "Remote filename",
       uploadFailed: "Upload failed, please try again",
+      conflictPolicy: {
+        overwrite: "Overwrite existing",
+        skip: "Skip existing",
+        rename: "Rename if exists",
+        skipIdentical: "Skip identical",
+      },
     },
   },
   fileDrag: {
@@ -229,7 +320,7 @@ export let zh = $state({
 
 1. 点击"Check"按钮验证存储桶连接
 2. 连接测试通过后，点击"Save"保存配置`,
-    title: "添加 Cloudflare R2 存储桶",
+    title: "添加存储桶",
     cancel: "取消",
     save: "保存",
     addNew: "添加新存储桶",
@@ -242,6 +333,43 @@ export let zh = $state({
       accessKey: "Access Key",
       secretKey: "Secret Key",
       customDomain: "自定义域名，例如 https://example.com",
+      endpoint: "Endpoint，AWS S3 可留空",
+      region: "区域，例如 us-east-1",
+      forcePathStyle: "路径风格 URL（MinIO 和大多数自建服务需要）",
+      jurisdiction: "管辖区",
+    },
+    jurisdictions: {
+      default: "默认",
+      eu: "欧盟",
+      fedramp: "FedRAMP",
+    },
+    types: {
+      r2: "Cloudflare R2",
+      s3: "S3 兼容",
+    },
+    advanced: {
+      title: "高级设置",
+      partSizeMb: "分段大小（MiB）",
+      auto: "自动",
+      checksum: "校验和",
+      checksumNone: "不计算",
+      retry: "重试",
+      maxRetries: "最大重试次数",
+      baseDelayMs: "初始间隔（毫秒）",
+      maxDelayMs: "最大间隔（毫秒）",
+      timeouts: "超时（秒）",
+      connectSecs: "连接",
+      readSecs: "读取",
+      operationSecs: "整个操作",
+      operationAttemptSecs: "单次尝试",
+      bandwidth: "上传限速",
+      headerRules: "请求头规则（JSON）",
+      headerRulesInvalid:
+        "请求头规则必须是由 { pattern, headers } 对象组成的 JSON 数组",
+      tls: "TLS",
+      caCertificates: "额外信任的 CA 证书（PEM）",
+      pinnedSha256: "固定的证书 SHA-256 指纹，每行一个",
+      skipVerify: "跳过证书校验（仅用于测试）",
     },
   },
   common: {
@@ -261,12 +389,49 @@ export let zh = $state({
     uploadError: "上传失败，请重试",
     noBucketWarning: "请先在设置中添加存储桶",
   },
+  vault: {
+    title: "密钥保险库",
+    machineKey: "密钥使用本设备派生的密钥加密",
+    passwordKey: "密钥使用主密码加密",
+    locked: "保险库已锁定，请输入主密码解锁",
+    create: "本设备无法获取机器标识，请设置主密码以创建保险库",
+    password: "主密码",
+    confirmPassword: "确认主密码",
+    passwordMismatch: "两次输入的密码不一致",
+    unlock: "解锁",
+    createVault: "创建保险库",
+    setPassword: "设置主密码",
+    changePassword: "修改主密码",
+    useMachineKey: "改用设备密钥",
+    passwordSaved: "主密码已保存",
+    machineKeySaved: "保险库已改用设备密钥",
+    reset: "重置保险库",
+    resetConfirm:
+      "重置会删除所有已保存的 Access Key 和 Secret Key，存储桶的其他设置会保留，但需要重新填写密钥。是否继续？",
+  },
+  pendingUploads: {
+    title: "未完成的上传",
+    description: "以下文件上次上传时被中断，可以继续上传或放弃已上传的分段",
+    resume: "继续",
+    discard: "放弃",
+    resumeAll: "全部继续",
+    discardAll: "全部放弃",
+    bucketMissing: "存储桶已不存在",
+  },
+  bandwidth: {
+    limit: "限速（MiB/s）",
+    unlimited: "不限速",
+    addRule: "添加时段规则",
+    scheduleHint: "在这些时段（本地时间）内，第一条匹配的规则代替上面的限速",
+  },
   settings: {
     buckets: "存储桶",
     language: "语言",
+    bandwidth: "全局上传限速",
     bucketDetails: {
       bucket: "存储桶",
       accountId: "账户 ID",
+      endpoint: "Endpoint",
     },
     defaultBucket: "默认存储桶",
     setDefault: "设为默认",
@@ -317,6 +482,9 @@ export let zh = $state({
       uploadComplete: "上传完成",
       uploadFailed: "上传失败：",
       cancelled: "已取消",
+      paused: "已暂停",
+      skippedExists: "已跳过，文件已存在",
+      skippedIdentical: "已跳过，已存在相同文件",
       waiting: "等待中...",
       copySuccess: "复制成功",
       copyFailed: "复制失败",
@@ -326,15 +494,26 @@ export let zh = $state({
       previous: "上一页",
       next: "下一页",
       cancel: "取消",
+      pause: "暂停",
+      resume: "继续",
+      pauseAll: "全部暂停",
+      resumeAll: "全部继续",
     },
     upload: {
       globalPath: "全局路径",
+      cacheControl: "缓存控制 Cache-Control",
       clear: "清空",
       upload: "上传",
       uploading: "上传中...",
       remotePath: "远程路径",
       remoteFilename: "远程文件名",
       uploadFailed: "上传失败，请重试",
+      conflictPolicy: {
+        overwrite: "覆盖已有文件",
+        skip: "跳过已有文件",
+        rename: "重名时自动改名",
+        skipIdentical: "跳过相同文件",
+      },
     },
   },
   fileDrag: {
@@ -24,6 +24,14 @@ export let globalState: GlobalState = $state({
     activated: true,
     trialStartDate: null,
     trialDays: 1000000,
+    bandwidthLimit: {
+      bytesPerSec: null,
+      schedule: [],
+    },
+    proxy: {
+      mode: "system",
+      noProxy: [],
+    },
   },
   progress: {},
 });
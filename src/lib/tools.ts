@@ -5,6 +5,7 @@ import clipboard from "tauri-plugin-clipboard-api";
 import { globalState, setAlert } from "./store.svelte";
 import type { FileDetail } from "./type";
 import { t } from "./i18n.svelte";
+import db from "./db";
 
 export function generateTimestamp() {
   const now = new Date();
@@ -162,3 +163,29 @@ export async function openDialogForDir() {
     await parsePaths(dialogFiles);
   }
 }
+
+// 旧版本把代理密码明文保存在应用设置中，迁移到保险库后从设置中删除
+export async function migrateProxyPassword() {
+  const proxy = globalState.appSetting.proxy as { password?: string };
+  if (!proxy.password) return;
+  try {
+    await invoke("vault_set_proxy_password", { password: proxy.password });
+    delete proxy.password;
+  } catch (error) {
+    console.error(error);
+  }
+}
+
+// 旧版本把密钥存在 IndexedDB 中，迁移到后端保险库后清空
+export async function migrateBucketSecrets() {
+  const buckets = await db.buckets.toArray();
+  for (const bucket of buckets) {
+    if (!bucket.id || (!bucket.accessKey && !bucket.secretKey)) continue;
+    try {
+      await invoke("vault_save_bucket", { bucketId: bucket.id, bucket });
+      await db.buckets.update(bucket.id, { accessKey: "", secretKey: "" });
+    } catch (error) {
+      console.error(error);
+    }
+  }
+}
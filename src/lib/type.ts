@@ -10,17 +10,90 @@ export interface Bucket {
   secretKey: string;
   customDomain: string;
   s3Api?: string;
-  [key: string]: string | number | undefined;
+  jurisdiction?: "default" | "eu" | "fedramp";
+  endpoint?: string;
+  region?: string;
+  forcePathStyle?: boolean;
+  retry?: RetryConfig;
+  timeouts?: Timeouts;
+  partSizeMb?: number;
+  bandwidthLimit?: BandwidthLimit;
+  checksum?: "none" | "sha256" | "crc32c";
+  headerRules?: HeaderRule[];
+  tls?: TlsOptions;
+  [key: string]: unknown;
+}
+
+// 自建服务的 TLS 设置，skipVerify 仅用于本地测试
+export interface TlsOptions {
+  caCertificates?: string;
+  pinnedSha256?: string[];
+  skipVerify?: boolean;
+}
+
+export interface BandwidthLimit {
+  bytesPerSec: number | null;
+  schedule: Array<{
+    start: string;
+    end: string;
+    bytesPerSec: number | null;
+  }>;
+}
+
+// 超时（秒），null 表示不限制；分段上传按分段大小和观测速率自动计算
+export interface Timeouts {
+  connectSecs?: number | null;
+  readSecs?: number | null;
+  operationSecs?: number | null;
+  operationAttemptSecs?: number | null;
+}
+
+export interface RetryConfig {
+  maxRetries: number;
+  baseDelayMs: number;
+  maxDelayMs: number;
 }
 
 export interface File {
   type: "text" | "image" | "file";
   id: string;
-  source: { filePath: string } | { fileContent: string };
+  source:
+    | { filePath: string }
+    | { fileContent: string }
+    | { base64: string }
+    | {
+        url: {
+          url: string;
+          headers?: Record<string, string>;
+          maxBytes?: number;
+        };
+      };
   remoteFilename: string;
   remoteFilenamePrefix: string;
+  headers?: ObjectHeaders;
 }
 
+export interface HeaderRule {
+  pattern: string;
+  headers: ObjectHeaders;
+}
+
+export interface ObjectHeaders {
+  contentType?: string;
+  cacheControl?: string;
+  contentDisposition?: string;
+  contentEncoding?: string;
+  contentLanguage?: string;
+  expires?: string;
+  metadata?: Record<string, string>;
+}
+
+export type ConflictPolicy =
+  | "overwrite"
+  | "skip"
+  | "rename"
+  | "skipIdentical";
+
 export interface FileDetail {
   id: string;
   path: string;
@@ -29,8 +102,14 @@ export interface FileDetail {
 }
 
 export type UploadStatus =
+  | "queued"
   | "success"
   | "cancelled"
+  | {
+      skipped: {
+        reason: "EXISTS" | "IDENTICAL";
+      };
+    }
   | {
       uploading: {
         progress: number;
@@ -39,6 +118,13 @@ export type UploadStatus =
         speed: number;
       };
     }
+  | {
+      paused: {
+        progress: number;
+        bytesUploaded: number;
+        totalBytes: number;
+      };
+    }
   | {
       error: {
         message: string;
@@ -68,6 +154,19 @@ export interface AppSettings {
   activated: boolean;
   trialStartDate: number | null;
   trialDays: number;
+  bandwidthLimit: BandwidthLimit;
+  proxy: ProxyConfig;
+}
+
+export type ProxyMode = "none" | "system" | "manual";
+
+export interface ProxyConfig {
+  mode: ProxyMode;
+  // http://、https://、socks5:// 或 socks5h://
+  url?: string;
+  username?: string;
+  // 密码保存在后端保险库中，通过 vault_set_proxy_password 设置
+  noProxy: string[];
 }
 
 export interface ModalState {
@@ -83,3 +182,136 @@ export interface UploadHistory {
   url: string;
   status: UploadStatus;
 }
+
+export interface UploadSession {
+  fileId: string;
+  bucketName: string;
+  endpoint: string;
+  domain: string;
+  path: string;
+  remoteFilename: string;
+  uploadId: string;
+  partSize: number;
+  checksum: "none" | "sha256" | "crc32c";
+  fileChecksum: string | null;
+  fingerprint: { size: number; modified: number };
+  completedParts: Array<{
+    partNumber: number;
+    eTag: string;
+    checksum: string | null;
+  }>;
+  createdAt: number;
+}
+
+// 启动时提示续传的会话，bucketId 为 null 表示对应的存储桶已被删除
+export interface PendingUpload extends UploadSession {
+  bucketId: number | null;
+}
+
+export interface ListOptions {
+  prefix?: string;
+  delimiter?: string;
+  continuationToken?: string;
+  maxKeys?: number;
+  search?: string;
+  sortBy?: "key" | "size" | "lastModified";
+  descending?: boolean;
+  // 逐个 HEAD 取 Content-Type，对象多时较慢
+  includeContentType?: boolean;
+}
+
+export interface ObjectEntry {
+  key: string;
+  size: number;
+  lastModified: number | null;
+  eTag: string | null;
+  contentType: string | null;
+}
+
+export interface ObjectPage {
+  folders: string[];
+  objects: ObjectEntry[];
+  nextContinuationToken: string | null;
+}
+
+export interface DeleteResult {
+  deleted: number;
+  failed: Array<{ key: string; code: string; message: string }>;
+}
+
+export interface DeleteProgress {
+  taskId: string;
+  deleted: number;
+  failed: number;
+  total: number | null;
+  done: boolean;
+}
+
+export interface CopyItem {
+  sourceKey: string;
+  targetKey: string;
+}
+
+export interface CopyResult {
+  copied: number;
+  failed: Array<{ sourceKey: string; targetKey: string; message: string }>;
+  deleted: number;
+  deleteFailed: Array<{ key: string; code: string; message: string }>;
+}
+
+export interface CopyProgress {
+  taskId: string;
+  copied: number;
+  failed: number;
+  total: number;
+  done: boolean;
+}
+
+export type DownloadStatus =
+  | "queued"
+  | "success"
+  | {
+      downloading: {
+        progress: number;
+        bytesDownloaded: number;
+        totalBytes: number;
+        speed: number;
+        eta: number | null;
+      };
+    }
+  | { error: { message: string } };
+
+export interface DownloadProgress {
+  taskId: string;
+  key: string;
+  path: string;
+  status: DownloadStatus;
+  timestamp: number;
+}
+
+export interface PresignOptions {
+  method?: "get" | "put";
+  expiresInSecs?: number;
+  responseContentDisposition?: string;
+  contentType?: string;
+}
+
+export interface PresignedUrl {
+  url: string;
+  method: string;
+  headers: Record<string, string>;
+  expiresAt: number;
+}
+
+// 后端密钥保险库的状态，password 模式下需要输入主密码解锁
+export type VaultKeySource = "machine" | "password";
+
+export interface VaultStatus {
+  locked: boolean;
+  keySource: VaultKeySource;
+  // 保险库文件是否已创建，未创建时解锁会用输入的主密码新建
+  initialized: boolean;
+  machineKeyAvailable: boolean;
+  // 打开或解锁失败的原因，例如文件损坏或机器标识变化
+  error: string | null;
+}